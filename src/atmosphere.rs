@@ -0,0 +1,123 @@
+//module for a global participating medium filling the whole scene, such as fog or haze
+use crate::materials::sample_henyey_greenstein;
+use crate::{Color, Point, Vec3};
+
+//coefficients are per unit distance at base_height. with a height falloff k the density
+//scales by exp(-k * (y - base_height)), so fog thins out with altitude
+#[derive(Debug, Clone, Copy)]
+pub struct Atmosphere {
+    pub sigma_a: Color,
+    pub sigma_s: Color,
+    pub falloff: f64,
+    pub base_height: f64,
+    pub g: f64,
+}
+
+impl Atmosphere {
+    pub fn new(sigma_a: Color, sigma_s: Color) -> Self {
+        Self {
+            sigma_a,
+            sigma_s,
+            falloff: 0.0,
+            base_height: 0.0,
+            g: 0.0,
+        }
+    }
+
+    pub fn with_height_falloff(mut self, falloff: f64, base_height: f64) -> Self {
+        self.falloff = falloff;
+        self.base_height = base_height;
+        self
+    }
+
+    //henyey-greenstein asymmetry of the in-scattering
+    pub fn with_anisotropy(mut self, g: f64) -> Self {
+        self.g = g.clamp(-0.99, 0.99);
+        self
+    }
+
+    pub fn sigma_t(&self) -> Color {
+        self.sigma_a + self.sigma_s
+    }
+
+    //relative density at the ray origin and how fast it changes per unit distance along
+    //the unit direction dir
+    fn profile(&self, origin: &Point, dir: &Vec3) -> (f64, f64) {
+        let rho = (-self.falloff * (origin.y - self.base_height)).exp();
+        (rho, self.falloff * dir.y)
+    }
+
+    //integral of the relative density over the first s units of distance
+    fn column(&self, origin: &Point, dir: &Vec3, s: f64) -> f64 {
+        let (rho, k) = self.profile(origin, dir);
+        if s == f64::INFINITY {
+            return if k > 1e-9 { rho / k } else { f64::INFINITY };
+        }
+        if k.abs() < 1e-9 {
+            rho * s
+        } else {
+            rho * (1.0 - (-k * s).exp()) / k
+        }
+    }
+
+    //per channel transmittance over the first s units of distance along unit dir
+    pub fn transmittance(&self, origin: &Point, dir: &Vec3, s: f64) -> Color {
+        let column = self.column(origin, dir, s);
+        let sigma_t = self.sigma_t();
+        let tr = |sigma: f64| {
+            if sigma == 0.0 {
+                1.0
+            } else {
+                (-sigma * column).exp()
+            }
+        };
+        Color::new(tr(sigma_t.x), tr(sigma_t.y), tr(sigma_t.z))
+    }
+
+    //distance to a collision sampled with the extinction of one channel by inverting the
+    //optical depth analytically, infinite when the ray escapes
+    fn sample_distance(&self, origin: &Point, dir: &Vec3, channel: i32) -> f64 {
+        let sigma = self.sigma_t().get(channel);
+        if sigma <= 0.0 {
+            return f64::INFINITY;
+        }
+        let (rho, k) = self.profile(origin, dir);
+        let tau = -(1.0 - crate::rand()).ln() / (sigma * rho);
+        if k.abs() < 1e-9 {
+            return tau;
+        }
+        let arg = 1.0 - tau * k;
+        if arg <= 0.0 {
+            f64::INFINITY
+        } else {
+            -arg.ln() / k
+        }
+    }
+
+    //samples the segment from origin along unit dir up to distance s_max (the nearest
+    //surface or infinity). returns the path weight and, for a scattering event, the
+    //scattering point and the new direction. the channel used for sampling is picked
+    //uniformly and the weight uses the average pdf over channels
+    pub fn sample(&self, origin: &Point, dir: &Vec3, s_max: f64) -> (Color, Option<(Point, Vec3)>) {
+        let channel = (3.0 * crate::rand()).min(2.0) as i32;
+        let s = self.sample_distance(origin, dir, channel);
+        let sigma_t = self.sigma_t();
+        if s < s_max {
+            let tr = self.transmittance(origin, dir, s);
+            let pdf = (sigma_t * tr).mean();
+            if pdf <= 0.0 {
+                return (Color::zero(), None);
+            }
+            let p = *origin + s * *dir;
+            let scattered = sample_henyey_greenstein(dir, self.g);
+            (self.sigma_s * tr / pdf, Some((p, scattered)))
+        } else {
+            let tr = self.transmittance(origin, dir, s_max);
+            let pdf = tr.mean();
+            if pdf <= 0.0 {
+                return (Color::zero(), None);
+            }
+            (tr / pdf, None)
+        }
+    }
+}
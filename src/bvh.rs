@@ -1,10 +1,12 @@
 //module for implementing bounding volume hierarchies
-use crate::{HitRecord, Hittable, HittableList, Point, Ray, Vec3};
+use crate::hittable::hit_opaque;
+use crate::{HitRecord, Hittable, HittableList, Point, Ray, Vec3, INFINITY};
 use std::mem;
+use std::sync::Arc;
 
 pub enum BVHContents {
     Node { left: Box<BVH>, right: Box<BVH> },
-    Leaf(Box<dyn Hittable>),
+    Leaf(Arc<dyn Hittable + Send>),
 }
 pub struct BVH {
     pub contents: BVHContents,
@@ -13,10 +15,10 @@ pub struct BVH {
 
 impl Hittable for BVH {
     //#[timed::timed(tracing(enabled = true), duration(disabled = true))]
-    fn hit(&self, r: &Ray, t_min: f64, mut t_max: f64) -> Option<HitRecord> {
+    fn hit(&self, r: &Ray, t_min: f64, mut t_max: f64) -> Option<HitRecord<'_>> {
         if self.bounding_box().hit(r, t_min, t_max) {
             match &self.contents {
-                BVHContents::Leaf(obj) => obj.hit(r, t_min, t_max),
+                BVHContents::Leaf(obj) => hit_opaque(obj.as_ref(), r, t_min, t_max),
                 BVHContents::Node { left, right } => {
                     let left = left.hit(r, t_min, t_max);
                     if let Some(HitRecord { t, .. }) = &left {
@@ -41,13 +43,37 @@ impl Hittable for BVH {
 }
 
 impl BVH {
-    pub fn new(mut objs: Vec<Box<dyn Hittable>>) -> Self {
-        fn axis_range(objs: &Vec<Box<dyn Hittable>>, dim: i32) -> f64 {
+    pub fn new(objs: Vec<Arc<dyn Hittable + Send>>) -> Self {
+        fn axis_range(objs: &Vec<Arc<dyn Hittable + Send>>, dim: i32) -> f64 {
             let range = objs.iter().fold(f64::MAX..f64::MIN, |acc, x| {
                 let bb = x.bounding_box();
-                f64::min(acc.start, bb.min.get(dim))..f64::min(acc.end, bb.max.get(dim))
+                f64::min(acc.start, bb.min.get(dim))..f64::max(acc.end, bb.max.get(dim))
             });
-            range.start - range.end
+            range.end - range.start
+        }
+
+        //unbounded objects have no centroid to sort by, so they sit in a list next to the
+        //hierarchy over everything else and are tested by every ray
+        let (unbounded, bounded): (Vec<_>, Vec<_>) = objs
+            .into_iter()
+            .partition(|obj| !obj.bounding_box().is_bounded());
+        let mut objs = bounded;
+        if !unbounded.is_empty() {
+            let list = BVH {
+                bounding_box: AABB::unbounded(),
+                contents: BVHContents::Leaf(Arc::new(HittableList { list: unbounded })),
+            };
+            if objs.is_empty() {
+                return list;
+            }
+            let hierarchy = BVH::new(objs);
+            return BVH {
+                bounding_box: AABB::unbounded(),
+                contents: BVHContents::Node {
+                    left: Box::new(list),
+                    right: Box::new(hierarchy),
+                },
+            };
         }
 
         match objs.len() {
@@ -58,7 +84,7 @@ impl BVH {
             },
             _ => {
                 //select axis to split on by max range
-                let axis = (0..3).into_iter().fold(0, |acc, x| {
+                let axis = (0..3).fold(0, |acc, x| {
                     if axis_range(&objs, x) > axis_range(&objs, acc) {
                         x
                     } else {
@@ -105,6 +131,20 @@ pub struct AABB {
 }
 
 impl AABB {
+    //box for infinite primitives such as planes
+    pub fn unbounded() -> AABB {
+        AABB {
+            min: Point::new(-INFINITY, -INFINITY, -INFINITY),
+            max: Point::new(INFINITY, INFINITY, INFINITY),
+        }
+    }
+
+    pub fn is_bounded(&self) -> bool {
+        [self.min, self.max]
+            .iter()
+            .all(|p| p.x.is_finite() && p.y.is_finite() && p.z.is_finite())
+    }
+
     pub fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> bool {
         for a in 0..3 {
             let inv_d = 1.0 / r.dir.get(a);
@@ -126,7 +166,25 @@ impl AABB {
 
     pub fn expand(&mut self, other: &AABB) {
         self.min = Vec3::min(&self.min, &other.min);
-        self.max = Vec3::max(&self.min, &other.min);
+        self.max = Vec3::max(&self.max, &other.max);
+    }
+
+    //widens any axis thinner than delta so flat primitives still get hit
+    pub fn pad(&self, delta: f64) -> AABB {
+        let widen = |min: f64, max: f64| {
+            if max - min < delta {
+                (min - delta / 2.0, max + delta / 2.0)
+            } else {
+                (min, max)
+            }
+        };
+        let (x0, x1) = widen(self.min.x, self.max.x);
+        let (y0, y1) = widen(self.min.y, self.max.y);
+        let (z0, z1) = widen(self.min.z, self.max.z);
+        AABB {
+            min: Point::new(x0, y0, z0),
+            max: Point::new(x1, y1, z1),
+        }
     }
 
     pub fn merge(bb1: &AABB, bb2: &AABB) -> AABB {
@@ -15,6 +15,7 @@ pub struct Camera {
     u: Vec3,
     v: Vec3,
     lens_radius: f64,
+    atmosphere: Option<Atmosphere>,
 }
 
 impl Camera {
@@ -36,7 +37,7 @@ impl Camera {
         let viewport_height = 2.0 * h;
         let viewport_width = aspect_ratio * viewport_height;
 
-        let origin = lookfrom.clone();
+        let origin = *lookfrom;
         let horizontal = focal_dist * viewport_width * u;
         let vertical = focal_dist * viewport_height * v;
         let lower_left_corner = origin - horizontal / 2.0 - vertical / 2.0 - focal_dist * w;
@@ -50,24 +51,40 @@ impl Camera {
             u,
             v,
             lens_radius: aperture / 2.0,
+            atmosphere: None,
         }
     }
 
-    fn get_ray(&self, s: f64, t: f64) -> Ray {
+    //fills the scene with a medium that the camera starts inside
+    pub fn with_atmosphere(mut self, atmosphere: Atmosphere) -> Self {
+        self.atmosphere = Some(atmosphere);
+        self
+    }
+
+    //ds and dt are the spacing between pixels, used to build the ray differentials
+    fn get_ray(&self, s: f64, t: f64, ds: f64, dt: f64) -> Ray {
         let rd = self.lens_radius * Vec3::random_in_unit_disk();
         let offset = self.u * rd.x + self.v * rd.y;
-        Ray::new(
-            self.origin + offset,
-            self.lower_left_corner + s * self.horizontal + t * self.vertical - self.origin - offset,
-        )
+        let origin = self.origin + offset;
+        let dir = self.lower_left_corner + s * self.horizontal + t * self.vertical - origin;
+        Ray::new(origin, dir).with_differential(RayDifferential {
+            rx_origin: origin,
+            rx_dir: dir + ds * self.horizontal,
+            ry_origin: origin,
+            ry_dir: dir + dt * self.vertical,
+        })
     }
 
     fn ray_cast(&self, i: i32, j: i32, height: i32, width: i32, world: &dyn Hittable) -> Color {
-        let u = (i as f64 + rand()) / (width - 1) as f64;
-        let v = (j as f64 + rand()) / (height - 1) as f64;
+        let ds = 1.0 / (width - 1) as f64;
+        let dt = 1.0 / (height - 1) as f64;
+        let u = (i as f64 + rand()) * ds;
+        let v = (j as f64 + rand()) * dt;
 
-        let r = self.get_ray(u, v);
-        ray_color(r, world, MAX_DEPTH)
+        let r = self
+            .get_ray(u, v, ds, dt)
+            .with_wavelengths(SampledWavelengths::sample_visible(rand()));
+        ray_color(r, world, self.atmosphere.as_ref(), MAX_DEPTH)
     }
 
     pub fn render(&self, height: i32, world: &dyn Hittable, n_samples: i32) {
@@ -108,21 +125,58 @@ impl Camera {
     }
 }
 
+//radiance arriving along a path, converted to rgb through the path's sampled wavelengths
+fn sensor_response(radiance: Color, wavelengths: &Option<SampledWavelengths>) -> Color {
+    match wavelengths {
+        Some(lambda) if !radiance.near_zero() => radiance * lambda.rgb_weight(),
+        _ => radiance,
+    }
+}
+
 //Rendering
-fn ray_color(r: Ray, world: &dyn Hittable, depth: i32) -> Color {
+fn ray_color(r: Ray, world: &dyn Hittable, atmosphere: Option<&Atmosphere>, depth: i32) -> Color {
     if depth > 0 {
-        match world.hit(&r, 0.001, INFINITY) {
-            Some(rec) => match rec.material.scatter(r, &rec) {
-                Some((attenuation, r_out)) => attenuation * ray_color(r_out, world, depth - 1),
-                None => Color::zero(),
-            },
-            None => {
-                let unit_dir = r.dir.unit();
-                let t = 0.5 * (unit_dir.y + 1.0);
-                Color::new(0.5, 0.7, 1.0);
-                (1.0 - t) * Color::new(1.0, 1.0, 1.0) + (t * Color::new(0.5, 0.7, 1.0))
+        let hit = world.hit(&r, 0.001, INFINITY);
+        //the atmosphere either scatters the ray before it reaches the surface (or background)
+        //or attenuates everything seen along the segment
+        let mut weight = Color::new(1.0, 1.0, 1.0);
+        if let Some(atmosphere) = atmosphere {
+            let length = r.dir.length();
+            let s_max = hit.as_ref().map_or(INFINITY, |rec| rec.t * length);
+            let (w, event) = atmosphere.sample(&r.origin, &(r.dir / length), s_max);
+            if let Some((p, dir)) = event {
+                let mut scattered = Ray::new(p, dir);
+                scattered.wavelengths = r.wavelengths;
+                return w * ray_color(scattered, world, Some(atmosphere), depth - 1);
             }
+            weight = w;
         }
+        weight
+            * match hit {
+                Some(rec) => {
+                    let wavelengths = r.wavelengths;
+                    let emitted = sensor_response(rec.material.emitted(&rec), &wavelengths);
+                    match rec.material.scatter(r, &rec) {
+                        Some((attenuation, mut r_out)) => {
+                            //scattered rays keep the path's wavelengths unless the material
+                            //terminated some of them
+                            if r_out.wavelengths.is_none() {
+                                r_out.wavelengths = wavelengths;
+                            }
+                            emitted + attenuation * ray_color(r_out, world, atmosphere, depth - 1)
+                        }
+                        None => emitted,
+                    }
+                }
+                None => {
+                    let unit_dir = r.dir.unit();
+                    let t = 0.5 * (unit_dir.y + 1.0);
+                    Color::new(0.5, 0.7, 1.0);
+                    let sky =
+                        (1.0 - t) * Color::new(1.0, 1.0, 1.0) + (t * Color::new(0.5, 0.7, 1.0));
+                    sensor_response(sky, &r.wavelengths)
+                }
+            }
     } else {
         Color::zero()
     }
@@ -0,0 +1,125 @@
+//module for capsules, cylinders closed with hemispheres
+use crate::frame::azimuth;
+use crate::hittable::{HitRecord, Hittable, Periodic};
+use crate::materials::*;
+use crate::polynomial::solve_quadratic;
+use crate::{Frame, Point, Ray, Vec3, AABB, PI};
+
+//all points within radius of the segment from a to b. u runs around the axis and v
+//along it, from the tip of the hemisphere at a to the tip of the one at b
+pub struct Capsule<M: Mat> {
+    pub a: Point,
+    pub height: f64,
+    pub radius: f64,
+    pub material: M,
+    //local space with the axis along z
+    frame: Frame,
+}
+
+impl<M: Mat> Capsule<M> {
+    pub fn new(a: Point, b: Point, radius: f64, material: M) -> Self {
+        let axis = b - a;
+        Self {
+            a,
+            height: axis.length(),
+            radius,
+            material,
+            frame: Frame::from_normal(axis.unit()),
+        }
+    }
+
+    fn get_uv(&self, p: &Point) -> (f64, f64) {
+        let local = self.frame.to_local(&(*p - self.a));
+        (
+            azimuth(&local) / (2.0 * PI),
+            (local.z + self.radius) / (self.height + 2.0 * self.radius),
+        )
+    }
+
+    //nearest hit in local space between t_min and t_max
+    fn intersect(&self, o: &Vec3, d: &Vec3, t_min: f64, t_max: f64) -> Option<f64> {
+        let mut closest: Option<f64> = None;
+        let mut consider = |t: f64| {
+            if t >= t_min && t <= t_max && closest.is_none_or(|best| t < best) {
+                closest = Some(t);
+            }
+        };
+        let r2 = self.radius * self.radius;
+
+        let a = d.x * d.x + d.y * d.y;
+        if a > 1e-12 {
+            let half_b = o.x * d.x + o.y * d.y;
+            let c = o.x * o.x + o.y * o.y - r2;
+            if let Some((t0, t1)) = solve_quadratic(a, half_b, c) {
+                for t in [t0, t1] {
+                    if (0.0..=self.height).contains(&(o.z + t * d.z)) {
+                        consider(t);
+                    }
+                }
+            }
+        }
+
+        //each hemisphere only counts beyond its end of the segment
+        for z in [0.0, self.height] {
+            let oc = *o - Vec3::new(0.0, 0.0, z);
+            if let Some((t0, t1)) = solve_quadratic(d.l2(), d.dot(&oc), oc.l2() - r2) {
+                for t in [t0, t1] {
+                    let hz = o.z + t * d.z;
+                    if (z == 0.0 && hz <= 0.0) || (z != 0.0 && hz >= self.height) {
+                        consider(t);
+                    }
+                }
+            }
+        }
+        closest
+    }
+}
+
+impl<M: Mat> Hittable for Capsule<M> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let o = self.frame.to_local(&(r.origin - self.a));
+        let d = self.frame.to_local(&r.dir);
+        let t = self.intersect(&o, &d, t_min, t_max)?;
+
+        let p = r.at(t);
+        let local = o + t * d;
+        let (u, v) = self.get_uv(&p);
+        let (sin_phi, cos_phi) = (2.0 * PI * u).sin_cos();
+        //the normal points away from the nearest point on the segment
+        let nearest = Vec3::new(0.0, 0.0, local.z.clamp(0.0, self.height));
+        let normal = (local - nearest) / self.radius;
+
+        //on the hemispheres the distance from the axis shrinks with height, which tilts
+        //the tangent along the axis
+        let offset = local.z - nearest.z;
+        let rho = (self.radius * self.radius - offset * offset)
+            .max(1e-18)
+            .sqrt();
+        let slope = -offset / rho;
+        let dpdu = 2.0 * PI * Vec3::new(-local.y, local.x, 0.0);
+        let dpdv =
+            (self.height + 2.0 * self.radius) * Vec3::new(slope * cos_phi, slope * sin_phi, 1.0);
+
+        Some(
+            HitRecord::new(
+                p,
+                t,
+                self.frame.to_world(&normal),
+                r,
+                &self.material,
+                (u, v),
+            )
+            .with_tangents(self.frame.to_world(&dpdu), self.frame.to_world(&dpdv))
+            .with_differentials(r, &|q: &Point| self.get_uv(q), Periodic::U),
+        )
+    }
+
+    fn bounding_box(&self) -> AABB {
+        let b = self.a + self.height * self.frame.n;
+        let extent = Vec3::new(self.radius, self.radius, self.radius);
+        AABB {
+            min: Vec3::min(&self.a, &b) - extent,
+            max: Vec3::max(&self.a, &b) + extent,
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//module for cones, optionally closed with a flat base
+use crate::frame::{azimuth, disk_extent};
+use crate::hittable::{HitRecord, Hittable, Periodic};
+use crate::materials::*;
+use crate::polynomial::solve_quadratic;
+use crate::{Frame, Point, Ray, Vec3, AABB, PI};
+
+#[derive(Copy, Clone)]
+enum Part {
+    Side,
+    Base,
+}
+
+//cone narrowing from a disk of radius at base to a point at apex. the side is
+//parameterized by the angle around the axis in u and the height in v, the base like a disk
+pub struct Cone<M: Mat> {
+    pub base: Point,
+    pub height: f64,
+    pub radius: f64,
+    pub capped: bool,
+    pub material: M,
+    //local space with the axis along z
+    frame: Frame,
+}
+
+impl<M: Mat> Cone<M> {
+    pub fn new(base: Point, apex: Point, radius: f64, material: M) -> Self {
+        let axis = apex - base;
+        Self {
+            base,
+            height: axis.length(),
+            radius,
+            capped: true,
+            material,
+            frame: Frame::from_normal(axis.unit()),
+        }
+    }
+
+    pub fn uncapped(mut self) -> Self {
+        self.capped = false;
+        self
+    }
+
+    fn get_uv(&self, part: Part, p: &Point) -> (f64, f64) {
+        let local = self.frame.to_local(&(*p - self.base));
+        let u = azimuth(&local) / (2.0 * PI);
+        match part {
+            Part::Side => (u, local.z / self.height),
+            Part::Base => (
+                u,
+                (local.x * local.x + local.y * local.y).sqrt() / self.radius,
+            ),
+        }
+    }
+
+    //nearest part hit in local space between t_min and t_max
+    fn intersect(&self, o: &Vec3, d: &Vec3, t_min: f64, t_max: f64) -> Option<(f64, Part)> {
+        let mut closest: Option<(f64, Part)> = None;
+        let mut consider = |t: f64, part: Part| {
+            if t >= t_min && t <= t_max && closest.is_none_or(|(best, _)| t < best) {
+                closest = Some((t, part));
+            }
+        };
+
+        //x^2 + y^2 = k^2 (h - z)^2 with k the radius shrink per unit height
+        let k2 = (self.radius / self.height).powi(2);
+        let w = self.height - o.z;
+        let a = d.x * d.x + d.y * d.y - k2 * d.z * d.z;
+        let half_b = o.x * d.x + o.y * d.y + k2 * w * d.z;
+        let c = o.x * o.x + o.y * o.y - k2 * w * w;
+        if let Some((t0, t1)) = solve_quadratic(a, half_b, c) {
+            for t in [t0, t1] {
+                let z = o.z + t * d.z;
+                //the quadric has a mirrored nappe above the apex, only keep the cone itself
+                if (0.0..=self.height).contains(&z) {
+                    consider(t, Part::Side);
+                }
+            }
+        }
+
+        if self.capped && d.z.abs() > 1e-12 {
+            let t = -o.z / d.z;
+            let x = o.x + t * d.x;
+            let y = o.y + t * d.y;
+            if x * x + y * y <= self.radius * self.radius {
+                consider(t, Part::Base);
+            }
+        }
+        closest
+    }
+}
+
+impl<M: Mat> Hittable for Cone<M> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let o = self.frame.to_local(&(r.origin - self.base));
+        let d = self.frame.to_local(&r.dir);
+        let (t, part) = self.intersect(&o, &d, t_min, t_max)?;
+
+        let p = r.at(t);
+        let local = o + t * d;
+        let (u, v) = self.get_uv(part, &p);
+        let (sin_phi, cos_phi) = (2.0 * PI * u).sin_cos();
+        let (normal, dpdu, dpdv) = match part {
+            Part::Side => {
+                let k = self.radius / self.height;
+                (
+                    Vec3::new(cos_phi, sin_phi, k).unit(),
+                    2.0 * PI * Vec3::new(-local.y, local.x, 0.0),
+                    Vec3::new(-self.radius * cos_phi, -self.radius * sin_phi, self.height),
+                )
+            }
+            Part::Base => (
+                Vec3::new(0.0, 0.0, -1.0),
+                2.0 * PI * v * self.radius * Vec3::new(-sin_phi, cos_phi, 0.0),
+                self.radius * Vec3::new(cos_phi, sin_phi, 0.0),
+            ),
+        };
+
+        Some(
+            HitRecord::new(
+                p,
+                t,
+                self.frame.to_world(&normal),
+                r,
+                &self.material,
+                (u, v),
+            )
+            .with_tangents(self.frame.to_world(&dpdu), self.frame.to_world(&dpdv))
+            .with_differentials(r, &|q: &Point| self.get_uv(part, q), Periodic::U),
+        )
+    }
+
+    fn bounding_box(&self) -> AABB {
+        let apex = self.base + self.height * self.frame.n;
+        let extent = disk_extent(&self.frame.n, self.radius);
+        AABB::merge(
+            &AABB {
+                min: self.base - extent,
+                max: self.base + extent,
+            },
+            &AABB {
+                min: apex,
+                max: apex,
+            },
+        )
+        .pad(0.0001)
+    }
+}
@@ -0,0 +1,143 @@
+//module for cylinders, optionally closed with flat caps
+use crate::frame::{azimuth, disk_extent};
+use crate::hittable::{HitRecord, Hittable, Periodic};
+use crate::materials::*;
+use crate::polynomial::solve_quadratic;
+use crate::{Frame, Point, Ray, Vec3, AABB, PI};
+
+#[derive(Copy, Clone)]
+enum Part {
+    Side,
+    Bottom,
+    Top,
+}
+
+//cylinder of radius around the segment from base to top. the side is parameterized by
+//the angle around the axis in u and the height in v, the caps like disks
+pub struct Cylinder<M: Mat> {
+    pub base: Point,
+    pub height: f64,
+    pub radius: f64,
+    pub capped: bool,
+    pub material: M,
+    //local space with the axis along z
+    frame: Frame,
+}
+
+impl<M: Mat> Cylinder<M> {
+    pub fn new(base: Point, top: Point, radius: f64, material: M) -> Self {
+        let axis = top - base;
+        Self {
+            base,
+            height: axis.length(),
+            radius,
+            capped: true,
+            material,
+            frame: Frame::from_normal(axis.unit()),
+        }
+    }
+
+    pub fn uncapped(mut self) -> Self {
+        self.capped = false;
+        self
+    }
+
+    fn get_uv(&self, part: Part, p: &Point) -> (f64, f64) {
+        let local = self.frame.to_local(&(*p - self.base));
+        let u = azimuth(&local) / (2.0 * PI);
+        match part {
+            Part::Side => (u, local.z / self.height),
+            Part::Bottom | Part::Top => (
+                u,
+                (local.x * local.x + local.y * local.y).sqrt() / self.radius,
+            ),
+        }
+    }
+
+    //nearest part hit in local space between t_min and t_max
+    fn intersect(&self, o: &Vec3, d: &Vec3, t_min: f64, t_max: f64) -> Option<(f64, Part)> {
+        let mut closest: Option<(f64, Part)> = None;
+        let mut consider = |t: f64, part: Part| {
+            if t >= t_min && t <= t_max && closest.is_none_or(|(best, _)| t < best) {
+                closest = Some((t, part));
+            }
+        };
+
+        let a = d.x * d.x + d.y * d.y;
+        let half_b = o.x * d.x + o.y * d.y;
+        let c = o.x * o.x + o.y * o.y - self.radius * self.radius;
+        if a > 1e-12 {
+            if let Some((t0, t1)) = solve_quadratic(a, half_b, c) {
+                for t in [t0, t1] {
+                    let z = o.z + t * d.z;
+                    if (0.0..=self.height).contains(&z) {
+                        consider(t, Part::Side);
+                    }
+                }
+            }
+        }
+
+        if self.capped && d.z.abs() > 1e-12 {
+            for (z, part) in [(0.0, Part::Bottom), (self.height, Part::Top)] {
+                let t = (z - o.z) / d.z;
+                let x = o.x + t * d.x;
+                let y = o.y + t * d.y;
+                if x * x + y * y <= self.radius * self.radius {
+                    consider(t, part);
+                }
+            }
+        }
+        closest
+    }
+}
+
+impl<M: Mat> Hittable for Cylinder<M> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let o = self.frame.to_local(&(r.origin - self.base));
+        let d = self.frame.to_local(&r.dir);
+        let (t, part) = self.intersect(&o, &d, t_min, t_max)?;
+
+        let p = r.at(t);
+        let local = o + t * d;
+        let (u, v) = self.get_uv(part, &p);
+        let (sin_phi, cos_phi) = (2.0 * PI * u).sin_cos();
+        let (normal, dpdu, dpdv) = match part {
+            Part::Side => (
+                Vec3::new(local.x, local.y, 0.0) / self.radius,
+                2.0 * PI * Vec3::new(-local.y, local.x, 0.0),
+                Vec3::new(0.0, 0.0, self.height),
+            ),
+            Part::Bottom | Part::Top => {
+                let rho = v * self.radius;
+                let z = if let Part::Top = part { 1.0 } else { -1.0 };
+                (
+                    Vec3::new(0.0, 0.0, z),
+                    2.0 * PI * rho * Vec3::new(-sin_phi, cos_phi, 0.0),
+                    self.radius * Vec3::new(cos_phi, sin_phi, 0.0),
+                )
+            }
+        };
+
+        Some(
+            HitRecord::new(
+                p,
+                t,
+                self.frame.to_world(&normal),
+                r,
+                &self.material,
+                (u, v),
+            )
+            .with_tangents(self.frame.to_world(&dpdu), self.frame.to_world(&dpdv))
+            .with_differentials(r, &|q: &Point| self.get_uv(part, q), Periodic::U),
+        )
+    }
+
+    fn bounding_box(&self) -> AABB {
+        let top = self.base + self.height * self.frame.n;
+        let extent = disk_extent(&self.frame.n, self.radius);
+        AABB {
+            min: Vec3::min(&self.base, &top) - extent,
+            max: Vec3::max(&self.base, &top) + extent,
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use crate::{Vec3, PI};
+
+//orthonormal basis with n as the local z axis
+#[derive(Copy, Clone, Debug)]
+pub struct Frame {
+    pub s: Vec3,
+    pub t: Vec3,
+    pub n: Vec3,
+}
+
+impl Frame {
+    //arbitrary tangents around unit vector n (Duff et al. 2017)
+    pub fn from_normal(n: Vec3) -> Self {
+        let sign = 1f64.copysign(n.z);
+        let a = -1.0 / (sign + n.z);
+        let b = n.x * n.y * a;
+        Self {
+            s: Vec3::new(1.0 + sign * n.x * n.x * a, sign * b, -sign * n.x),
+            t: Vec3::new(b, sign + n.y * n.y * a, -n.y),
+            n,
+        }
+    }
+
+    //tangent s follows the projection of dir onto the plane of n, falling back to
+    //arbitrary tangents when dir is (nearly) parallel to n
+    pub fn from_normal_tangent(n: Vec3, dir: Vec3) -> Self {
+        let s = dir - dir.dot(&n) * n;
+        if s.l2() < 1e-16 {
+            return Self::from_normal(n);
+        }
+        let s = s.unit();
+        Self {
+            s,
+            t: n.cross(&s),
+            n,
+        }
+    }
+
+    pub fn to_local(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(v.dot(&self.s), v.dot(&self.t), v.dot(&self.n))
+    }
+
+    pub fn to_world(&self, v: &Vec3) -> Vec3 {
+        v.x * self.s + v.y * self.t + v.z * self.n
+    }
+}
+
+//angle of a local point around the z axis in [0, 2 pi)
+pub(crate) fn azimuth(local: &Vec3) -> f64 {
+    let phi = local.y.atan2(local.x);
+    if phi < 0.0 {
+        phi + 2.0 * PI
+    } else {
+        phi
+    }
+}
+
+//half extents of the axis aligned box around a circle of radius r facing along unit n,
+//r * sqrt(1 - n_i^2) along each axis
+pub(crate) fn disk_extent(n: &Vec3, radius: f64) -> Vec3 {
+    radius
+        * Vec3::new(
+            (1.0 - n.x * n.x).max(0.0).sqrt(),
+            (1.0 - n.y * n.y).max(0.0).sqrt(),
+            (1.0 - n.z * n.z).max(0.0).sqrt(),
+        )
+}
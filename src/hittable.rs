@@ -1,6 +1,34 @@
 use crate::materials::*;
-use crate::{Point, Ray, Vec3, AABB};
+use crate::{Frame, Point, Ray, RayDifferential, Vec3, AABB};
+use std::sync::Arc;
 
+//screen space derivatives of the hit point and its uvs, estimated from ray differentials.
+//all zero when the incoming ray carried no differentials
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Footprint {
+    pub dpdx: Vec3,
+    pub dpdy: Vec3,
+    pub dudx: f64,
+    pub dvdx: f64,
+    pub dudy: f64,
+    pub dvdy: f64,
+}
+
+//which uv coordinates of a primitive wrap around with period 1, e.g. the angle around a
+//sphere. unbounded or clamped coordinates must not be wrapped when taking differences
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Periodic {
+    pub u: bool,
+    pub v: bool,
+}
+
+impl Periodic {
+    pub const NONE: Periodic = Periodic { u: false, v: false };
+    pub const U: Periodic = Periodic { u: true, v: false };
+    pub const UV: Periodic = Periodic { u: true, v: true };
+}
+
+#[derive(Copy, Clone)]
 pub struct HitRecord<'a> {
     pub normal: Vec3,
     pub p: Point,
@@ -9,13 +37,32 @@ pub struct HitRecord<'a> {
     pub v: f64,
     pub front_face: bool,
     pub material: &'a dyn Mat,
+    pub footprint: Footprint,
+    //partial derivatives of p with respect to u and v, zero if the primitive has no
+    //parameterization
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
+    //orthonormal shading frame, frame.n is always equal to normal and frame.s follows dpdu
+    pub frame: Frame,
 }
 
 pub trait Hittable: Sync {
-    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>>;
     fn bounding_box(&self) -> AABB;
 }
 
+//shared objects, e.g. a prototype placed many times through instances. an arc is only
+//sync when what it points to is also send, so shared objects must be send
+impl<H: Hittable + Send + ?Sized> Hittable for Arc<H> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        hit_opaque(self.as_ref(), r, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.as_ref().bounding_box()
+    }
+}
+
 impl<'a> HitRecord<'a> {
     pub fn new(
         p: Point,
@@ -23,47 +70,157 @@ impl<'a> HitRecord<'a> {
         outward_norm: Vec3,
         r: &Ray,
         material: &'a dyn Mat,
-        uv: &dyn Fn(&Point) -> (f64, f64),
+        uv: (f64, f64),
     ) -> HitRecord<'a> {
         let mut res = HitRecord {
             normal: Vec3::zero(),
             p,
             t,
-            u: 0.0,
-            v: 0.0,
+            u: uv.0,
+            v: uv.1,
             front_face: false,
             material,
+            footprint: Footprint::default(),
+            dpdu: Vec3::zero(),
+            dpdv: Vec3::zero(),
+            frame: Frame::from_normal(outward_norm),
         };
         res.set_face_normal(r, outward_norm);
-        let (u, v) = uv(&res.normal);
-        res.u = u;
-        res.v = v;
         res
     }
-    pub fn set_face_normal(&mut self, r: &Ray, outward_norm: Vec3) {
-        match self {
-            HitRecord {
-                front_face, normal, ..
-            } => {
-                *front_face = r.dir.dot(&outward_norm) < 0.0;
-                *normal = if *front_face {
-                    outward_norm
-                } else {
-                    -outward_norm
-                };
+
+    pub fn with_tangents(mut self, dpdu: Vec3, dpdv: Vec3) -> Self {
+        self.dpdu = dpdu;
+        self.dpdv = dpdv;
+        self.frame = Frame::from_normal_tangent(self.normal, dpdu);
+        self
+    }
+
+    //replaces the shading normal, e.g. for bump mapping, keeping the frame in sync
+    pub fn set_shading_normal(&mut self, n: Vec3) {
+        self.normal = n;
+        self.frame = Frame::from_normal_tangent(n, self.dpdu);
+    }
+
+    pub fn to_local(&self, v: &Vec3) -> Vec3 {
+        self.frame.to_local(v)
+    }
+
+    pub fn to_world(&self, v: &Vec3) -> Vec3 {
+        self.frame.to_world(v)
+    }
+
+    //intersects the ray's differentials with the tangent plane at p and maps the offset
+    //points through uv_at. differences in a periodic coordinate are taken modulo 1 so the
+    //mapping stays continuous across its seam
+    pub fn with_differentials(
+        mut self,
+        r: &Ray,
+        uv_at: &dyn Fn(&Point) -> (f64, f64),
+        periodic: Periodic,
+    ) -> Self {
+        let Some(diff) = r.differential else {
+            return self;
+        };
+        let plane_hit = |origin: &Point, dir: &Vec3| -> Option<Point> {
+            let denom = self.normal.dot(dir);
+            if denom.abs() < 1e-12 {
+                return None;
             }
+            let t = self.normal.dot(&(self.p - *origin)) / denom;
+            Some(*origin + t * *dir)
+        };
+        let (Some(px), Some(py)) = (
+            plane_hit(&diff.rx_origin, &diff.rx_dir),
+            plane_hit(&diff.ry_origin, &diff.ry_dir),
+        ) else {
+            return self;
+        };
+
+        let wrap = |d: f64, periodic: bool| if periodic { d - d.round() } else { d };
+        let (u, v) = uv_at(&self.p);
+        let (ux, vx) = uv_at(&px);
+        let (uy, vy) = uv_at(&py);
+        self.footprint = Footprint {
+            dpdx: px - self.p,
+            dpdy: py - self.p,
+            dudx: wrap(ux - u, periodic.u),
+            dvdx: wrap(vx - v, periodic.v),
+            dudy: wrap(uy - u, periodic.u),
+            dvdy: wrap(vy - v, periodic.v),
+        };
+        self
+    }
+
+    //spawns a specularly scattered ray. the incoming ray's differentials are carried across
+    //the bounce by offsetting their origins over the footprint and bending their
+    //directions the same way as the main ray
+    pub fn specular_ray(&self, r_in: &Ray, dir: Vec3, bend: impl Fn(&Vec3) -> Vec3) -> Ray {
+        let scattered = Ray::new(self.p, dir);
+        match r_in.differential {
+            Some(diff) => scattered.with_differential(RayDifferential {
+                rx_origin: self.p + self.footprint.dpdx,
+                rx_dir: bend(&diff.rx_dir.unit()),
+                ry_origin: self.p + self.footprint.dpdy,
+                ry_dir: bend(&diff.ry_dir.unit()),
+            }),
+            None => scattered,
         }
     }
+
+    pub fn set_face_normal(&mut self, r: &Ray, outward_norm: Vec3) {
+        self.front_face = r.dir.dot(&outward_norm) < 0.0;
+        self.normal = if self.front_face {
+            outward_norm
+        } else {
+            -outward_norm
+        };
+        self.frame = Frame::from_normal_tangent(self.normal, self.dpdu);
+    }
+}
+
+//uniform number in [0, 1) determined by the hit alone, so that nested hierarchies which
+//test the same hit again (lists inside lists, instances) all reach the same decision
+fn hit_hash(rec: &HitRecord) -> f64 {
+    let mut h = rec.t.to_bits() ^ rec.u.to_bits().rotate_left(21) ^ rec.v.to_bits().rotate_left(42);
+    //splitmix64 finalizer
+    h = (h ^ (h >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94d049bb133111eb);
+    h ^= h >> 31;
+    (h >> 11) as f64 / (1u64 << 53) as f64
+}
+
+//hits obj, stochastically passing through cutout regions of its material and moving on
+//to the next hit along the ray
+pub fn hit_opaque<'a, H: Hittable + ?Sized>(
+    obj: &'a H,
+    r: &Ray,
+    mut t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord<'a>> {
+    loop {
+        let rec = obj.hit(r, t_min, t_max)?;
+        let alpha = rec.material.alpha(&rec);
+        if alpha >= 1.0 || (alpha > 0.0 && hit_hash(&rec) < alpha) {
+            return Some(rec);
+        }
+        t_min = rec.t + 1e-6;
+    }
 }
 
+//objects are held through arcs so that one object can sit in several lists or bvhs
 #[derive(Default)]
 pub struct HittableList {
-    pub list: Vec<Box<dyn Hittable>>,
+    pub list: Vec<Arc<dyn Hittable + Send>>,
 }
 
 impl HittableList {
-    pub fn add(&mut self, h: impl Hittable + 'static) {
-        self.list.push(Box::new(h));
+    pub fn add(&mut self, h: impl Hittable + Send + 'static) {
+        self.list.push(Arc::new(h));
+    }
+
+    pub fn add_shared(&mut self, h: Arc<dyn Hittable + Send>) {
+        self.list.push(h);
     }
 
     pub fn clear(&mut self) {
@@ -73,12 +230,12 @@ impl HittableList {
 
 impl Hittable for HittableList {
     //#[timed::timed(tracing(enabled = true), duration(disabled = true))]
-    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
         let mut closest_so_far = t_max;
         let mut obj_hit = None;
 
         for obj in self.list.iter() {
-            let res = obj.hit(r, t_min, closest_so_far);
+            let res = hit_opaque(obj.as_ref(), r, t_min, closest_so_far);
             if let Some(HitRecord { t, .. }) = res {
                 closest_so_far = t;
                 obj_hit = res;
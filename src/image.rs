@@ -1,6 +1,6 @@
 use crate::Vec3;
 use std::fs::File;
-use std::io::Write;
+use std::io::{self, BufRead, BufReader, Read, Write};
 use std::ops::Add;
 use std::vec::Vec;
 
@@ -44,6 +44,19 @@ impl Image {
         }
     }
 
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    //pixels are stored top row first
+    pub fn get(&self, x: i32, y: i32) -> Vec3 {
+        self.image[(y * self.width + x) as usize]
+    }
+
     pub fn push(&mut self, pixel_color: Vec3) {
         self.image.push(pixel_color);
     }
@@ -86,4 +99,353 @@ impl Image {
         let ib = (255.999 * color.z) as i32;
         writeln!(file, "{ir} {ig} {ib} ").unwrap();
     }
+
+    //loads a .ppm (P3/P6), .png or .hdr (radiance rgbe) file, dispatching on extension.
+    //8-bit formats are normalized to [0, 1] but left in their stored (usually srgb) encoding
+    pub fn load(path: &str) -> io::Result<Self> {
+        let ext = path.rsplit('.').next().unwrap_or("").to_ascii_lowercase();
+        let mut reader = BufReader::new(File::open(path)?);
+        match ext.as_str() {
+            "ppm" => Self::read_ppm(&mut reader),
+            "png" => Self::read_png(reader),
+            "hdr" => Self::read_hdr(&mut reader),
+            _ => Err(invalid_data(format!("unsupported image format: {path}"))),
+        }
+    }
+
+    fn read_ppm(reader: &mut impl BufRead) -> io::Result<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        //header is whitespace separated tokens with optional # comments
+        let mut pos = 0;
+        let mut next_token = |bytes: &[u8]| -> io::Result<String> {
+            loop {
+                while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+                    pos += 1;
+                }
+                if pos < bytes.len() && bytes[pos] == b'#' {
+                    while pos < bytes.len() && bytes[pos] != b'\n' {
+                        pos += 1;
+                    }
+                } else {
+                    break;
+                }
+            }
+            let start = pos;
+            while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            if start == pos {
+                return Err(invalid_data("unexpected end of ppm file".to_string()));
+            }
+            Ok(String::from_utf8_lossy(&bytes[start..pos]).into_owned())
+        };
+
+        let magic = next_token(&bytes)?;
+        let width = parse_int(&next_token(&bytes)?)?;
+        let height = parse_int(&next_token(&bytes)?)?;
+        let n = pixel_count(width, height)?;
+        let max_val = parse_int(&next_token(&bytes)?)?;
+        if !(1..=65535).contains(&max_val) {
+            return Err(invalid_data(format!("invalid ppm max value {max_val}")));
+        }
+        let max_val = max_val as f64;
+
+        let mut img = Image::new(height, width);
+        match magic.as_str() {
+            "P3" => {
+                for _ in 0..n {
+                    let r = parse_int(&next_token(&bytes)?)? as f64;
+                    let g = parse_int(&next_token(&bytes)?)? as f64;
+                    let b = parse_int(&next_token(&bytes)?)? as f64;
+                    img.push(Vec3::new(r, g, b) / max_val);
+                }
+            }
+            "P6" => {
+                //a single whitespace byte separates the header from the raster
+                let data = &bytes[(pos + 1).min(bytes.len())..];
+                let stride = if max_val < 256.0 { 1 } else { 2 };
+                if data.len() < n * 3 * stride {
+                    return Err(invalid_data("truncated ppm raster".to_string()));
+                }
+                let sample = |i: usize| -> f64 {
+                    if stride == 1 {
+                        data[i] as f64
+                    } else {
+                        u16::from_be_bytes([data[2 * i], data[2 * i + 1]]) as f64
+                    }
+                };
+                for i in 0..n {
+                    img.push(
+                        Vec3::new(sample(3 * i), sample(3 * i + 1), sample(3 * i + 2)) / max_val,
+                    );
+                }
+            }
+            _ => return Err(invalid_data(format!("unsupported ppm type {magic}"))),
+        }
+        Ok(img)
+    }
+
+    fn read_png(reader: impl Read) -> io::Result<Self> {
+        let mut decoder = png::Decoder::new(reader);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut png_reader = decoder.read_info().map_err(io::Error::other)?;
+        let mut buf = vec![0; png_reader.output_buffer_size()];
+        let info = png_reader.next_frame(&mut buf).map_err(io::Error::other)?;
+
+        let channels = info.color_type.samples();
+        let mut img = Image::new(info.height as i32, info.width as i32);
+        for pixel in buf[..info.buffer_size()].chunks_exact(channels) {
+            let color = match info.color_type {
+                png::ColorType::Grayscale | png::ColorType::GrayscaleAlpha => {
+                    Vec3::new(pixel[0] as f64, pixel[0] as f64, pixel[0] as f64)
+                }
+                _ => Vec3::new(pixel[0] as f64, pixel[1] as f64, pixel[2] as f64),
+            };
+            img.push(color / 255.0);
+        }
+        Ok(img)
+    }
+
+    fn read_hdr(reader: &mut impl BufRead) -> io::Result<Self> {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        if !line.starts_with("#?") {
+            return Err(invalid_data("missing radiance header".to_string()));
+        }
+        //header ends with a blank line
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                return Err(invalid_data("unexpected end of hdr header".to_string()));
+            }
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                break;
+            }
+            if trimmed.starts_with("FORMAT=") && trimmed != "FORMAT=32-bit_rle_rgbe" {
+                return Err(invalid_data(format!("unsupported hdr {trimmed}")));
+            }
+        }
+
+        //only the standard top to bottom, left to right orientation is supported
+        line.clear();
+        reader.read_line(&mut line)?;
+        let dims: Vec<&str> = line.split_whitespace().collect();
+        if dims.len() != 4 || dims[0] != "-Y" || dims[2] != "+X" {
+            return Err(invalid_data(format!("unsupported hdr resolution {line}")));
+        }
+        let height = parse_int(dims[1])?;
+        let width = parse_int(dims[3])?;
+        pixel_count(width, height)?;
+
+        let mut img = Image::new(height, width);
+        let mut scanline = vec![[0u8; 4]; width as usize];
+        for _ in 0..height {
+            read_hdr_scanline(reader, &mut scanline)?;
+            for rgbe in &scanline {
+                img.push(rgbe_to_color(rgbe));
+            }
+        }
+        Ok(img)
+    }
+}
+
+pub(crate) fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn parse_int(token: &str) -> io::Result<i32> {
+    token
+        .parse()
+        .map_err(|_| invalid_data(format!("expected integer, found {token}")))
+}
+
+//largest image the loaders accept, well above 8k but small enough to allocate
+const MAX_PIXELS: usize = 1 << 26;
+
+//rejects empty, negative or absurdly large dimensions before anything is allocated
+fn pixel_count(width: i32, height: i32) -> io::Result<usize> {
+    if width <= 0 || height <= 0 {
+        return Err(invalid_data(format!("invalid image size {width}x{height}")));
+    }
+    (width as usize)
+        .checked_mul(height as usize)
+        .filter(|&n| n <= MAX_PIXELS)
+        .ok_or_else(|| invalid_data(format!("image too large: {width}x{height}")))
+}
+
+fn rgbe_to_color(rgbe: &[u8; 4]) -> Vec3 {
+    if rgbe[3] == 0 {
+        return Vec3::zero();
+    }
+    let f = 2f64.powi(rgbe[3] as i32 - 136);
+    Vec3::new(rgbe[0] as f64, rgbe[1] as f64, rgbe[2] as f64) * f
+}
+
+fn read_hdr_scanline(reader: &mut impl Read, scanline: &mut [[u8; 4]]) -> io::Result<()> {
+    let width = scanline.len();
+    let mut head = [0u8; 4];
+    reader.read_exact(&mut head)?;
+
+    //new style rle stores each channel separately, flagged by a leading 2 2
+    let is_rle = (8..0x8000).contains(&width)
+        && head[0] == 2
+        && head[1] == 2
+        && ((head[2] as usize) << 8 | head[3] as usize) == width;
+    if !is_rle {
+        scanline[0] = head;
+        for pixel in scanline[1..].iter_mut() {
+            reader.read_exact(pixel)?;
+        }
+        return Ok(());
+    }
+
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let mut count = [0u8; 1];
+            reader.read_exact(&mut count)?;
+            let run = count[0] > 128;
+            let len = if run {
+                count[0] as usize - 128
+            } else {
+                count[0] as usize
+            };
+            if len == 0 || x + len > width {
+                return Err(invalid_data("corrupt hdr scanline".to_string()));
+            }
+            if run {
+                let mut value = [0u8; 1];
+                reader.read_exact(&mut value)?;
+                for pixel in scanline[x..x + len].iter_mut() {
+                    pixel[channel] = value[0];
+                }
+            } else {
+                let mut values = vec![0u8; len];
+                reader.read_exact(&mut values)?;
+                for (pixel, value) in scanline[x..x + len].iter_mut().zip(values) {
+                    pixel[channel] = value;
+                }
+            }
+            x += len;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_pixel(img: &Image, x: i32, y: i32, expected: (f64, f64, f64)) {
+        let p = img.get(x, y);
+        let (r, g, b) = expected;
+        assert!(
+            (p.x - r).abs() < 1e-9 && (p.y - g).abs() < 1e-9 && (p.z - b).abs() < 1e-9,
+            "pixel ({x}, {y}) is {p:?}, expected {expected:?}"
+        );
+    }
+
+    #[test]
+    fn ppm_ascii() {
+        let data = b"P3\n# comment\n2 1\n255\n255 0 0  0 51 255\n";
+        let img = Image::read_ppm(&mut &data[..]).unwrap();
+        assert_eq!((img.width(), img.height()), (2, 1));
+        assert_pixel(&img, 0, 0, (1.0, 0.0, 0.0));
+        assert_pixel(&img, 1, 0, (0.0, 0.2, 1.0));
+    }
+
+    #[test]
+    fn ppm_binary_8_bit() {
+        let mut data = b"P6 1 2 255\n".to_vec();
+        data.extend_from_slice(&[255, 0, 51, 0, 255, 0]);
+        let img = Image::read_ppm(&mut &data[..]).unwrap();
+        assert_pixel(&img, 0, 0, (1.0, 0.0, 0.2));
+        assert_pixel(&img, 0, 1, (0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn ppm_binary_16_bit() {
+        //samples above 255 take two big endian bytes each
+        let mut data = b"P6 2 1 65535\n".to_vec();
+        data.extend_from_slice(&[0xff, 0xff, 0, 0, 0x80, 0]);
+        data.extend_from_slice(&[0, 0, 0xff, 0xff, 0, 0]);
+        let img = Image::read_ppm(&mut &data[..]).unwrap();
+        assert_pixel(&img, 0, 0, (1.0, 0.0, 32768.0 / 65535.0));
+        assert_pixel(&img, 1, 0, (0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn ppm_truncated() {
+        let data = b"P6 2 2 255\n\x00\x00\x00";
+        assert!(Image::read_ppm(&mut &data[..]).is_err());
+    }
+
+    #[test]
+    fn ppm_malformed_header() {
+        for data in [
+            &b"P3 -1 2 255\n"[..],
+            b"P3 2 0 255\n",
+            b"P3 100000 100000 255\n",
+            b"P3 1 1 0\n0 0 0\n",
+            b"P6 1 1 70000\n\x00\x00\x00\x00\x00\x00",
+        ] {
+            assert!(Image::read_ppm(&mut &data[..]).is_err(), "{data:?}");
+        }
+    }
+
+    #[test]
+    fn hdr_malformed_resolution() {
+        for dims in ["-Y 1 +X 0", "-Y -2 +X 4", "-Y 100000 +X 100000"] {
+            let data = format!("#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n{dims}\n");
+            assert!(Image::read_hdr(&mut data.as_bytes()).is_err(), "{dims}");
+        }
+    }
+
+    #[test]
+    fn hdr_flat_scanline() {
+        //scanlines narrower than 8 pixels are never run length encoded
+        let mut data = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 2\n".to_vec();
+        data.extend_from_slice(&[128, 64, 0, 129, 1, 2, 3, 136]);
+        let img = Image::read_hdr(&mut &data[..]).unwrap();
+        assert_eq!((img.width(), img.height()), (2, 1));
+        assert_pixel(&img, 0, 0, (1.0, 0.5, 0.0));
+        assert_pixel(&img, 1, 0, (1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn hdr_rle_scanline() {
+        let mut data = vec![2, 2, 0, 8];
+        //red as a literal packet, the rest as runs
+        data.extend_from_slice(&[8, 0, 1, 2, 3, 4, 5, 6, 7]);
+        data.extend_from_slice(&[128 + 8, 2]);
+        data.extend_from_slice(&[128 + 8, 3]);
+        data.extend_from_slice(&[128 + 8, 136]);
+        let mut scanline = [[0u8; 4]; 8];
+        read_hdr_scanline(&mut &data[..], &mut scanline).unwrap();
+        for (x, pixel) in scanline.iter().enumerate() {
+            assert_eq!(*pixel, [x as u8, 2, 3, 136]);
+        }
+    }
+
+    #[test]
+    fn hdr_flat_scanline_at_rle_width() {
+        //wide scanlines without the 2 2 marker are stored flat
+        let data: Vec<u8> = (0..8).flat_map(|x| [x, 1, 2, 136]).collect();
+        let mut scanline = [[0u8; 4]; 8];
+        read_hdr_scanline(&mut &data[..], &mut scanline).unwrap();
+        for (x, pixel) in scanline.iter().enumerate() {
+            assert_eq!(*pixel, [x as u8, 1, 2, 136]);
+        }
+    }
+
+    #[test]
+    fn hdr_corrupt_run() {
+        //run longer than the scanline
+        let data = [2, 2, 0, 8, 128 + 9, 0];
+        let mut scanline = [[0u8; 4]; 8];
+        assert!(read_hdr_scanline(&mut &data[..], &mut scanline).is_err());
+    }
 }
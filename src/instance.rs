@@ -0,0 +1,81 @@
+//module for placing hittables in the world with an affine transform
+use crate::hittable::{hit_opaque, HitRecord, Hittable};
+use crate::{Frame, Point, Ray, RayDifferential, Transform, Vec3, AABB, INFINITY};
+
+//wraps an object defined in its own space. rays are moved into object space for the hit
+//and the record is moved back out, so t is the same in both spaces
+pub struct Instance<H: Hittable> {
+    pub object: H,
+    pub transform: Transform,
+}
+
+impl<H: Hittable> Instance<H> {
+    pub fn new(object: H, transform: Transform) -> Self {
+        Self { object, transform }
+    }
+
+    //the builders below apply after the transforms already on the instance
+    pub fn transformed(mut self, transform: Transform) -> Self {
+        self.transform = self.transform.then(&transform);
+        self
+    }
+
+    pub fn translate(self, offset: Vec3) -> Self {
+        self.transformed(Transform::translate(offset))
+    }
+
+    pub fn rotate(self, axis: Vec3, degrees: f64) -> Self {
+        self.transformed(Transform::rotate(axis, degrees))
+    }
+
+    pub fn scale(self, factors: Vec3) -> Self {
+        self.transformed(Transform::scale(factors))
+    }
+}
+
+impl<H: Hittable> Hittable for Instance<H> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let m = &self.transform;
+        let mut local = Ray::new(m.inverse_point(&r.origin), m.inverse_vector(&r.dir));
+        local.wavelengths = r.wavelengths;
+        local.differential = r.differential.map(|diff| RayDifferential {
+            rx_origin: m.inverse_point(&diff.rx_origin),
+            rx_dir: m.inverse_vector(&diff.rx_dir),
+            ry_origin: m.inverse_point(&diff.ry_origin),
+            ry_dir: m.inverse_vector(&diff.ry_dir),
+        });
+
+        let mut rec = hit_opaque(&self.object, &local, t_min, t_max)?;
+        rec.p = m.point(&rec.p);
+        //the normal already faces against the ray, which the inverse transpose preserves
+        rec.normal = m.normal(&rec.normal).unit();
+        rec.dpdu = m.vector(&rec.dpdu);
+        rec.dpdv = m.vector(&rec.dpdv);
+        rec.footprint.dpdx = m.vector(&rec.footprint.dpdx);
+        rec.footprint.dpdy = m.vector(&rec.footprint.dpdy);
+        rec.frame = Frame::from_normal_tangent(rec.normal, rec.dpdu);
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> AABB {
+        let bb = self.object.bounding_box();
+        if !bb.is_bounded() {
+            return AABB::unbounded();
+        }
+        //box around the eight transformed corners
+        let mut res = AABB {
+            min: Point::new(INFINITY, INFINITY, INFINITY),
+            max: Point::new(-INFINITY, -INFINITY, -INFINITY),
+        };
+        for i in 0..8 {
+            let corner = Point::new(
+                if i & 1 == 0 { bb.min.x } else { bb.max.x },
+                if i & 2 == 0 { bb.min.y } else { bb.max.y },
+                if i & 4 == 0 { bb.min.z } else { bb.max.z },
+            );
+            let p = self.transform.point(&corner);
+            res = AABB::merge(&res, &AABB { min: p, max: p });
+        }
+        res
+    }
+}
@@ -1,27 +1,54 @@
+mod atmosphere;
 mod bvh;
 mod camera;
+mod capsule;
+mod cone;
+mod cylinder;
+mod frame;
 mod hittable;
 mod image;
+mod instance;
+mod medium;
+mod microfacet;
 mod perlin;
+mod plane;
+mod polynomial;
+mod quad;
 mod ray;
+mod spectrum;
 mod sphere;
+mod torus;
+mod transform;
 mod vec3;
 
 pub mod materials;
 pub mod scenes;
 pub mod texture;
 
+pub use atmosphere::Atmosphere;
 pub use bvh::{AABB, BVH};
 pub use camera::Camera;
-pub use hittable::{HitRecord, Hittable, HittableList};
+pub use capsule::Capsule;
+pub use cone::Cone;
+pub use cylinder::Cylinder;
+pub use frame::Frame;
+pub use hittable::{Footprint, HitRecord, Hittable, HittableList, Periodic};
 pub use image::Image;
+pub use instance::Instance;
+pub use medium::{ConstantMedium, HeterogeneousMedium};
 pub use perlin::*;
-pub use ray::Ray;
+pub use plane::{Disk, Plane};
+pub use quad::{make_box, Quad};
+pub use ray::{Ray, RayDifferential};
+pub use spectrum::{Dispersion, SampledWavelengths};
 pub use sphere::Sphere;
 pub use std::f64::consts::PI;
-pub use std::f64::INFINITY;
+pub use torus::Torus;
+pub use transform::Transform;
 pub use vec3::{Color, Point, Vec3};
 
+pub const INFINITY: f64 = f64::INFINITY;
+
 use rand::Rng;
 pub fn rand() -> f64 {
     let mut rng = rand::thread_rng();
@@ -7,6 +7,13 @@ fn main() {
     const SAMPLES_PER_PIXEL: i32 = 100;
     const _BVH: bool = false;
 
-    let (camera, world) = scenes::two_perlin_spheres();
+    //scene picked by name on the command line, e.g. cargo run --release -- forest
+    let (camera, world) = match std::env::args().nth(1).as_deref() {
+        Some("random") => scenes::random_scene(),
+        Some("checkered") => scenes::checkered(),
+        Some("cornell") => scenes::cornell_box(),
+        Some("forest") => scenes::forest(),
+        _ => scenes::two_perlin_spheres(),
+    };
     camera.render(IMAGE_HEIGHT, &world, SAMPLES_PER_PIXEL);
 }
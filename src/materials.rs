@@ -1,9 +1,24 @@
-use crate::texture::Texture;
-use crate::{Color, HitRecord, Ray, Vec3};
+use crate::hittable::hit_opaque;
+use crate::microfacet::*;
+use crate::texture::{Solid, Texture};
+use crate::{
+    Color, Dispersion, Frame, HitRecord, Hittable, Point, Ray, SampledWavelengths, Vec3, AABB,
+    INFINITY,
+};
 
 pub trait Mat: Sync {
     //returns Some of Color (attenuation) and Ray (scatter dir) or None
     fn scatter(&self, r_in: Ray, rec: &HitRecord) -> Option<(Color, Ray)>;
+
+    //light given off at the hit, black unless overridden
+    fn emitted(&self, _rec: &HitRecord) -> Color {
+        Color::zero()
+    }
+
+    //opacity at the hit, rays pass through with probability 1 - alpha
+    fn alpha(&self, _rec: &HitRecord) -> f64 {
+        1.0
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -25,7 +40,60 @@ impl<T: Texture> Mat for Lambertian<T> {
             scatter_dir = rec.normal
         }
         let scattered = Ray::new(rec.p, scatter_dir);
-        Some((self.albedo.value(rec.u, rec.v, &rec.p), scattered))
+        let albedo = self
+            .albedo
+            .value_filtered(rec.u, rec.v, &rec.p, &rec.footprint);
+        Some((albedo, scattered))
+    }
+}
+
+//rough diffuse (Oren and Nayar 1994). sigma is the standard deviation of the facet
+//slope angle in radians, read as the mean of the sigma texture's channels
+#[derive(Copy, Clone)]
+pub struct OrenNayar<T: Texture, S: Texture> {
+    pub albedo: T,
+    pub sigma: S,
+}
+
+impl<T: Texture, S: Texture> OrenNayar<T, S> {
+    pub fn new(albedo: T, sigma: S) -> Self {
+        Self { albedo, sigma }
+    }
+}
+
+impl<T: Texture, S: Texture> Mat for OrenNayar<T, S> {
+    fn scatter(&self, r_in: Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
+        let wo = rec.to_local(&(-r_in.dir.unit()));
+        let wi = cosine_local();
+
+        let sigma = self
+            .sigma
+            .value_filtered(rec.u, rec.v, &rec.p, &rec.footprint)
+            .mean()
+            .max(0.0);
+        let sigma2 = sigma * sigma;
+        let a = 1.0 - 0.5 * sigma2 / (sigma2 + 0.33);
+        let b = 0.45 * sigma2 / (sigma2 + 0.09);
+
+        let sin_i = (1.0 - wi.z * wi.z).max(0.0).sqrt();
+        let sin_o = (1.0 - wo.z * wo.z).max(0.0).sqrt();
+        let mut angular = 0.0;
+        if sin_i > 1e-4 && sin_o > 1e-4 && wo.z > 0.0 {
+            let cos_phi = (wi.x * wo.x + wi.y * wo.y) / (sin_i * sin_o);
+            //alpha is the larger polar angle, beta the smaller
+            let (sin_alpha, tan_beta) = if wi.z > wo.z {
+                (sin_o, sin_i / wi.z)
+            } else {
+                (sin_i, sin_o / wo.z)
+            };
+            angular = cos_phi.max(0.0) * sin_alpha * tan_beta;
+        }
+
+        let albedo = self
+            .albedo
+            .value_filtered(rec.u, rec.v, &rec.p, &rec.footprint);
+        let scattered = Ray::new(rec.p, rec.to_world(&wi));
+        Some(((a + b * angular) * albedo, scattered))
     }
 }
 
@@ -55,9 +123,10 @@ impl Metal {
 impl Mat for Metal {
     fn scatter(&self, r_in: Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
         let reflected = reflect(&r_in.dir.unit(), &rec.normal);
-        let scattered = Ray::new(
-            rec.p,
+        let scattered = rec.specular_ray(
+            &r_in,
             reflected + self.fuzz * Vec3::rand_within_unit_sphere(),
+            |d| reflect(d, &rec.normal),
         );
         if scattered.dir.dot(&rec.normal) > 0.0 {
             Some((self.albedo, scattered))
@@ -67,6 +136,92 @@ impl Mat for Metal {
     }
 }
 
+//rough conductor with a ggx microfacet distribution and complex ior (eta + ik) per
+//channel. roughness is perceptually linear in [0, 1], separately along dpdu and dpdv
+pub struct Conductor {
+    pub eta: Color,
+    pub k: Color,
+    distribution: TrowbridgeReitz,
+}
+
+impl Conductor {
+    pub fn new(eta: Color, k: Color, roughness: f64) -> Self {
+        Self::anisotropic(eta, k, roughness, roughness)
+    }
+
+    pub fn anisotropic(eta: Color, k: Color, roughness_u: f64, roughness_v: f64) -> Self {
+        Self {
+            eta,
+            k,
+            distribution: TrowbridgeReitz::new(
+                TrowbridgeReitz::roughness_to_alpha(roughness_u),
+                TrowbridgeReitz::roughness_to_alpha(roughness_v),
+            ),
+        }
+    }
+
+    //measured ior sampled at roughly 650, 550 and 450nm
+    pub fn gold(roughness: f64) -> Self {
+        Self::new(
+            Color::new(0.143, 0.374, 1.442),
+            Color::new(3.983, 2.385, 1.603),
+            roughness,
+        )
+    }
+
+    pub fn copper(roughness: f64) -> Self {
+        Self::new(
+            Color::new(0.200, 0.924, 1.102),
+            Color::new(3.912, 2.452, 2.142),
+            roughness,
+        )
+    }
+
+    pub fn aluminium(roughness: f64) -> Self {
+        Self::new(
+            Color::new(1.657, 0.880, 0.521),
+            Color::new(9.224, 6.270, 4.837),
+            roughness,
+        )
+    }
+
+    pub fn silver(roughness: f64) -> Self {
+        Self::new(
+            Color::new(0.155, 0.117, 0.138),
+            Color::new(4.828, 3.122, 2.147),
+            roughness,
+        )
+    }
+}
+
+impl Mat for Conductor {
+    fn scatter(&self, r_in: Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
+        let wo = rec.to_local(&(-r_in.dir.unit()));
+        if wo.z <= 0.0 {
+            return None;
+        }
+
+        if self.distribution.effectively_smooth() {
+            let wi = Vec3::new(-wo.x, -wo.y, wo.z);
+            let f = fresnel_conductor(wo.z, &self.eta, &self.k);
+            let scattered = rec.specular_ray(&r_in, rec.to_world(&wi), |d| reflect(d, &rec.normal));
+            return Some((f, scattered));
+        }
+
+        //sampling visible normals leaves only fresnel and the ratio of masking terms
+        let wm = self
+            .distribution
+            .sample_wm(&wo, crate::rand(), crate::rand());
+        let wi = reflect_local(&wo, &wm);
+        if wi.z <= 0.0 {
+            return None;
+        }
+        let f = fresnel_conductor(wo.dot(&wm), &self.eta, &self.k);
+        let weight = self.distribution.g(&wo, &wi) / self.distribution.g1(&wo);
+        Some((weight * f, Ray::new(rec.p, rec.to_world(&wi))))
+    }
+}
+
 //Dielectrics
 fn refract(uv: &Vec3, n: &Vec3, etai_over_etat: f64) -> Vec3 {
     let cos_theta = f64::min((-*uv).dot(n), 1.0);
@@ -79,24 +234,83 @@ fn reflectance(cos: f64, ref_idx: f64) -> f64 {
     let r0 = ((1.0 - ref_idx) / (1.0 + ref_idx)).powi(2);
     r0 + (1.0 - r0) * (1.0 - cos).powi(5)
 }
+
+//beer-lambert transmittance over distance through a medium with absorption sigma_a
+fn beer_lambert(sigma_a: &Color, distance: f64) -> Color {
+    Color::new(
+        (-sigma_a.x * distance).exp(),
+        (-sigma_a.y * distance).exp(),
+        (-sigma_a.z * distance).exp(),
+    )
+}
+
+//absorption coefficient that leaves color after travelling distance through a medium
+fn absorption_for(color: Color, distance: f64) -> Color {
+    let sigma = |c: f64| -c.clamp(1e-6, 1.0).ln() / distance;
+    Color::new(sigma(color.x), sigma(color.y), sigma(color.z))
+}
+
+//a hit on a back face means the ray travelled inside the object to get there
+fn interior_transmittance(sigma_a: &Color, r_in: &Ray, rec: &HitRecord) -> Color {
+    if rec.front_face {
+        Color::new(1.0, 1.0, 1.0)
+    } else {
+        beer_lambert(sigma_a, rec.t * r_in.dir.length())
+    }
+}
+
 pub struct Dielectric {
     pub ir: f64,
+    //absorption coefficient per unit distance inside the object
+    pub absorption: Color,
+    //wavelength dependent ior, overrides ir when present
+    pub dispersion: Option<Dispersion>,
 }
 
 impl Dielectric {
     pub fn new(ir: f64) -> Self {
-        Self { ir }
+        Self {
+            ir,
+            absorption: Color::zero(),
+            dispersion: None,
+        }
+    }
+
+    pub fn dispersive(dispersion: Dispersion) -> Self {
+        Self {
+            dispersion: Some(dispersion),
+            ..Self::new(dispersion.nominal_ior())
+        }
+    }
+
+    pub fn with_absorption(mut self, absorption: Color) -> Self {
+        self.absorption = absorption;
+        self
+    }
+
+    //glass that filters white light to color over distance, deepening with thickness
+    pub fn colored(ir: f64, color: Color, distance: f64) -> Self {
+        Self::new(ir).with_absorption(absorption_for(color, distance))
     }
 }
 
 impl Mat for Dielectric {
     fn scatter(&self, r_in: Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
-        let att = Color::new(1.0, 1.0, 1.0);
-        let refraction_ratio = if rec.front_face {
-            1.0 / self.ir
-        } else {
-            self.ir
+        let att = interior_transmittance(&self.absorption, &r_in, rec);
+
+        //dispersion refracts each wavelength differently, so only the hero wavelength
+        //carries on past a dispersive interface
+        let mut wavelengths = r_in.wavelengths;
+        let ir = match &self.dispersion {
+            None => self.ir,
+            Some(dispersion) => {
+                let lambda = wavelengths
+                    .get_or_insert_with(|| SampledWavelengths::sample_visible(crate::rand()));
+                lambda.terminate_secondary();
+                dispersion.ior(lambda.hero())
+            }
         };
+        let refraction_ratio = if rec.front_face { 1.0 / ir } else { ir };
 
         let unit_dir = r_in.dir.unit();
 
@@ -105,12 +319,889 @@ impl Mat for Dielectric {
 
         let cannot_refract = refraction_ratio * sin_theta > 1.0;
 
-        let dir = if cannot_refract || reflectance(cos_theta, refraction_ratio) > crate::rand() {
-            reflect(&unit_dir, &rec.normal)
+        let mut scattered =
+            if cannot_refract || reflectance(cos_theta, refraction_ratio) > crate::rand() {
+                rec.specular_ray(&r_in, reflect(&unit_dir, &rec.normal), |d| {
+                    reflect(d, &rec.normal)
+                })
+            } else {
+                rec.specular_ray(
+                    &r_in,
+                    refract(&unit_dir, &rec.normal, refraction_ratio),
+                    |d| refract(d, &rec.normal, refraction_ratio),
+                )
+            };
+        scattered.wavelengths = wavelengths;
+        Some((att, scattered))
+    }
+}
+
+//perturbs the shading normal of the wrapped material by the gradient of a height
+//texture, estimated with forward differences in u and v
+pub struct BumpMapped<M: Mat, T: Texture> {
+    pub inner: M,
+    pub height: T,
+    pub scale: f64,
+}
+
+impl<M: Mat, T: Texture> BumpMapped<M, T> {
+    pub fn new(inner: M, height: T, scale: f64) -> Self {
+        Self {
+            inner,
+            height,
+            scale,
+        }
+    }
+
+    fn height_at(&self, rec: &HitRecord, du: f64, dv: f64) -> f64 {
+        let p = rec.p + du * rec.dpdu + dv * rec.dpdv;
+        self.height
+            .value_filtered(rec.u + du, rec.v + dv, &p, &rec.footprint)
+            .mean()
+    }
+}
+
+impl<M: Mat, T: Texture> Mat for BumpMapped<M, T> {
+    fn scatter(&self, r_in: Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
+        //step about half a pixel when differentials are available
+        let fp = &rec.footprint;
+        let mut du = 0.5 * (fp.dudx.abs() + fp.dudy.abs());
+        let mut dv = 0.5 * (fp.dvdx.abs() + fp.dvdy.abs());
+        if du == 0.0 {
+            du = 0.0005;
+        }
+        if dv == 0.0 {
+            dv = 0.0005;
+        }
+
+        let h = self.height_at(rec, 0.0, 0.0);
+        let dhdu = (self.height_at(rec, du, 0.0) - h) / du;
+        let dhdv = (self.height_at(rec, 0.0, dv) - h) / dv;
+
+        let dpdu = rec.dpdu + self.scale * dhdu * rec.normal;
+        let dpdv = rec.dpdv + self.scale * dhdv * rec.normal;
+        let n = dpdu.cross(&dpdv);
+        if n.near_zero() {
+            return self.inner.scatter(r_in, rec);
+        }
+
+        let mut shading = *rec;
+        shading.set_shading_normal(if n.dot(&rec.normal) < 0.0 {
+            -n.unit()
+        } else {
+            n.unit()
+        });
+        self.inner.scatter(r_in, &shading)
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Color {
+        self.inner.emitted(rec)
+    }
+
+    fn alpha(&self, rec: &HitRecord) -> f64 {
+        self.inner.alpha(rec)
+    }
+}
+
+//replaces the shading normal with one read from a tangent space normal map, where
+//rgb in [0, 1] encodes xyz in [-1, 1] along (dpdu, dpdv, normal). the map should be
+//loaded with a linear color space
+pub struct NormalMapped<M: Mat, T: Texture> {
+    pub inner: M,
+    pub normals: T,
+}
+
+impl<M: Mat, T: Texture> NormalMapped<M, T> {
+    pub fn new(inner: M, normals: T) -> Self {
+        Self { inner, normals }
+    }
+}
+
+impl<M: Mat, T: Texture> Mat for NormalMapped<M, T> {
+    fn scatter(&self, r_in: Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
+        let n = rec.normal;
+        let tangent = rec.dpdu - rec.dpdu.dot(&n) * n;
+        if tangent.near_zero() {
+            return self.inner.scatter(r_in, rec);
+        }
+        let tangent = tangent.unit();
+        let mut bitangent = n.cross(&tangent);
+        if bitangent.dot(&rec.dpdv) < 0.0 {
+            bitangent = -bitangent;
+        }
+
+        let c = self
+            .normals
+            .value_filtered(rec.u, rec.v, &rec.p, &rec.footprint);
+        let local = 2.0 * c - Vec3::new(1.0, 1.0, 1.0);
+        let mapped = local.x * tangent + local.y * bitangent + local.z * n;
+        if mapped.near_zero() {
+            return self.inner.scatter(r_in, rec);
+        }
+
+        let mut shading = *rec;
+        shading.set_shading_normal(mapped.unit());
+        self.inner.scatter(r_in, &shading)
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Color {
+        self.inner.emitted(rec)
+    }
+
+    fn alpha(&self, rec: &HitRecord) -> f64 {
+        self.inner.alpha(rec)
+    }
+}
+
+//samples reflection or transmission through a rough dielectric interface, choosing
+//between them by fresnel. returns the local direction and its weight (f * cos / pdf)
+fn sample_rough_dielectric(
+    distribution: &TrowbridgeReitz,
+    eta: f64,
+    wo: &Vec3,
+) -> Option<(Vec3, f64)> {
+    let smooth = distribution.effectively_smooth();
+    let wm = if smooth {
+        Vec3::new(0.0, 0.0, 1.0)
+    } else {
+        distribution.sample_wm(wo, crate::rand(), crate::rand())
+    };
+
+    let f = fresnel_dielectric(wo.dot(&wm), eta);
+    let wi = if crate::rand() < f {
+        let wi = reflect_local(wo, &wm);
+        if wi.z <= 0.0 {
+            return None;
+        }
+        wi
+    } else {
+        let wi = refract_local(wo, &wm, eta)?;
+        if wi.z >= 0.0 {
+            return None;
+        }
+        wi
+    };
+
+    if smooth {
+        Some((wi, 1.0))
+    } else {
+        Some((wi, distribution.g(wo, &wi) / distribution.g1(wo)))
+    }
+}
+
+//rough dielectric interface with a ggx distribution (Walter et al. 2007). each
+//transmission through the interface is multiplied by tint
+pub struct RoughDielectric {
+    pub ir: f64,
+    pub tint: Color,
+    pub absorption: Color,
+    distribution: TrowbridgeReitz,
+}
+
+impl RoughDielectric {
+    pub fn new(ir: f64, roughness: f64) -> Self {
+        let alpha = TrowbridgeReitz::roughness_to_alpha(roughness);
+        Self {
+            ir,
+            tint: Color::new(1.0, 1.0, 1.0),
+            absorption: Color::zero(),
+            distribution: TrowbridgeReitz::new(alpha, alpha),
+        }
+    }
+
+    pub fn with_tint(mut self, tint: Color) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    pub fn with_absorption(mut self, absorption: Color) -> Self {
+        self.absorption = absorption;
+        self
+    }
+
+    pub fn colored(ir: f64, roughness: f64, color: Color, distance: f64) -> Self {
+        Self::new(ir, roughness).with_absorption(absorption_for(color, distance))
+    }
+}
+
+impl Mat for RoughDielectric {
+    fn scatter(&self, r_in: Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
+        //the shading normal faces the incoming ray, so eta is relative to its side
+        let eta = if rec.front_face {
+            self.ir
         } else {
-            refract(&unit_dir, &rec.normal, refraction_ratio)
+            1.0 / self.ir
+        };
+        let wo = rec.to_local(&(-r_in.dir.unit()));
+        if wo.z <= 0.0 {
+            return None;
+        }
+
+        let absorbed = interior_transmittance(&self.absorption, &r_in, rec);
+        let (wi, weight) = sample_rough_dielectric(&self.distribution, eta, &wo)?;
+        let att = if wi.z < 0.0 {
+            weight * absorbed * self.tint
+        } else {
+            weight * absorbed
+        };
+
+        let dir = rec.to_world(&wi);
+        if !self.distribution.effectively_smooth() {
+            return Some((att, Ray::new(rec.p, dir)));
+        }
+        let scattered = if wi.z > 0.0 {
+            rec.specular_ray(&r_in, dir, |d| reflect(d, &rec.normal))
+        } else {
+            rec.specular_ray(&r_in, dir, |d| refract(d, &rec.normal, 1.0 / eta))
         };
-        let scattered = Ray::new(rec.p, dir);
         Some((att, scattered))
     }
 }
+
+fn luminance(c: &Color) -> f64 {
+    0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z
+}
+
+fn lerp(a: Color, b: Color, t: f64) -> Color {
+    (1.0 - t) * a + t * b
+}
+
+fn schlick(f0: Color, cos: f64) -> Color {
+    let m = (1.0 - cos).clamp(0.0, 1.0).powi(5);
+    f0 + m * (Color::new(1.0, 1.0, 1.0) - f0)
+}
+
+//cosine distributed direction about the local normal, same construction as Lambertian
+fn cosine_local() -> Vec3 {
+    let dir = Vec3::new(0.0, 0.0, 1.0) + Vec3::rand_within_unit_sphere().unit();
+    if dir.near_zero() {
+        Vec3::new(0.0, 0.0, 1.0)
+    } else {
+        dir.unit()
+    }
+}
+
+//principled uber material in the style of the disney brdf (Burley 2012, 2015). every
+//parameter is a texture so importers can map material files onto it; scalar parameters
+//read the mean of the texture's channels. the lobes are sampled stochastically
+pub struct Principled {
+    pub base_color: Box<dyn Texture>,
+    pub metallic: Box<dyn Texture>,
+    pub roughness: Box<dyn Texture>,
+    pub specular: Box<dyn Texture>,
+    pub specular_tint: Box<dyn Texture>,
+    pub sheen: Box<dyn Texture>,
+    pub sheen_tint: Box<dyn Texture>,
+    pub clearcoat: Box<dyn Texture>,
+    pub transmission: Box<dyn Texture>,
+    pub ior: Box<dyn Texture>,
+    pub emission: Box<dyn Texture>,
+}
+
+fn gray(v: f64) -> Box<dyn Texture> {
+    Box::new(Solid::new(v, v, v))
+}
+
+impl Principled {
+    pub fn new(base_color: impl Texture + 'static) -> Self {
+        Self {
+            base_color: Box::new(base_color),
+            metallic: gray(0.0),
+            roughness: gray(0.5),
+            specular: gray(0.5),
+            specular_tint: gray(0.0),
+            sheen: gray(0.0),
+            sheen_tint: gray(0.5),
+            clearcoat: gray(0.0),
+            transmission: gray(0.0),
+            ior: gray(1.5),
+            emission: gray(0.0),
+        }
+    }
+
+    pub fn metallic(mut self, t: impl Texture + 'static) -> Self {
+        self.metallic = Box::new(t);
+        self
+    }
+
+    pub fn roughness(mut self, t: impl Texture + 'static) -> Self {
+        self.roughness = Box::new(t);
+        self
+    }
+
+    pub fn specular(mut self, t: impl Texture + 'static) -> Self {
+        self.specular = Box::new(t);
+        self
+    }
+
+    pub fn specular_tint(mut self, t: impl Texture + 'static) -> Self {
+        self.specular_tint = Box::new(t);
+        self
+    }
+
+    pub fn sheen(mut self, t: impl Texture + 'static) -> Self {
+        self.sheen = Box::new(t);
+        self
+    }
+
+    pub fn sheen_tint(mut self, t: impl Texture + 'static) -> Self {
+        self.sheen_tint = Box::new(t);
+        self
+    }
+
+    pub fn clearcoat(mut self, t: impl Texture + 'static) -> Self {
+        self.clearcoat = Box::new(t);
+        self
+    }
+
+    pub fn transmission(mut self, t: impl Texture + 'static) -> Self {
+        self.transmission = Box::new(t);
+        self
+    }
+
+    pub fn ior(mut self, t: impl Texture + 'static) -> Self {
+        self.ior = Box::new(t);
+        self
+    }
+
+    pub fn emission(mut self, t: impl Texture + 'static) -> Self {
+        self.emission = Box::new(t);
+        self
+    }
+}
+
+//clearcoat is a fixed, fairly glossy polyurethane-like layer
+const CLEARCOAT_ALPHA: f64 = 0.01;
+
+impl Mat for Principled {
+    fn scatter(&self, r_in: Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
+        let wo = rec.to_local(&(-r_in.dir.unit()));
+        if wo.z <= 0.0 {
+            return None;
+        }
+
+        let color = |t: &dyn Texture| t.value_filtered(rec.u, rec.v, &rec.p, &rec.footprint);
+        let scalar = |t: &dyn Texture| color(t).mean().clamp(0.0, 1.0);
+        let base = color(self.base_color.as_ref());
+        let metallic = scalar(self.metallic.as_ref());
+        let roughness = scalar(self.roughness.as_ref());
+        let specular = scalar(self.specular.as_ref());
+        let specular_tint = scalar(self.specular_tint.as_ref());
+        let sheen = scalar(self.sheen.as_ref());
+        let sheen_tint = scalar(self.sheen_tint.as_ref());
+        let clearcoat = scalar(self.clearcoat.as_ref());
+        let transmission = scalar(self.transmission.as_ref());
+        let ior = color(self.ior.as_ref()).mean().max(1.0);
+
+        let white = Color::new(1.0, 1.0, 1.0);
+        let lum = luminance(&base);
+        let tint = if lum > 0.0 { base / lum } else { white };
+        let spec_f0 = lerp(
+            0.08 * specular * lerp(white, tint, specular_tint),
+            base,
+            metallic,
+        );
+        let sheen_color = sheen * lerp(white, tint, sheen_tint);
+        let alpha = TrowbridgeReitz::roughness_to_alpha(roughness).max(1e-3);
+        let distribution = TrowbridgeReitz::new(alpha, alpha);
+
+        //lobe weights, and selection probabilities roughly proportional to their albedo
+        let w_diffuse = (1.0 - metallic) * (1.0 - transmission);
+        let w_specular = 1.0 - (1.0 - metallic) * transmission;
+        let w_transmission = (1.0 - metallic) * transmission;
+        let w_clearcoat = 0.25 * clearcoat;
+        let p = [
+            w_diffuse * (lum + sheen),
+            w_specular * luminance(&schlick(spec_f0, wo.z)),
+            w_transmission,
+            w_clearcoat * luminance(&schlick(Color::new(0.04, 0.04, 0.04), wo.z)),
+        ];
+        let total: f64 = p.iter().sum();
+        if total <= 0.0 {
+            return None;
+        }
+
+        //rounding can leave pick past the running sum, so stop at the last lobe that can
+        //actually be chosen rather than landing on one with zero probability
+        let last = p.iter().rposition(|&w| w > 0.0).unwrap_or(0);
+        let mut pick = crate::rand() * total;
+        let mut lobe = 0;
+        while lobe < last && pick >= p[lobe] {
+            pick -= p[lobe];
+            lobe += 1;
+        }
+        let select = p[lobe] / total;
+
+        let (wi, estimate) = match lobe {
+            0 => {
+                let wi = cosine_local();
+                let h = (wo + wi).unit();
+                let cos_d = wi.dot(&h);
+                let fd90 = 0.5 + 2.0 * roughness * cos_d * cos_d;
+                let fl = 1.0 + (fd90 - 1.0) * (1.0 - wi.z).powi(5);
+                let fv = 1.0 + (fd90 - 1.0) * (1.0 - wo.z).powi(5);
+                let sheen_term = crate::PI * (1.0 - cos_d).powi(5) * sheen_color;
+                (wi, w_diffuse * (fl * fv * base + sheen_term))
+            }
+            1 => {
+                let wm = distribution.sample_wm(&wo, crate::rand(), crate::rand());
+                let wi = reflect_local(&wo, &wm);
+                if wi.z <= 0.0 {
+                    return None;
+                }
+                let g = distribution.g(&wo, &wi) / distribution.g1(&wo);
+                (wi, w_specular * g * schlick(spec_f0, wo.dot(&wm)))
+            }
+            2 => {
+                let eta = if rec.front_face { ior } else { 1.0 / ior };
+                let (wi, weight) = sample_rough_dielectric(&distribution, eta, &wo)?;
+                let att = if wi.z < 0.0 { base } else { white };
+                (wi, w_transmission * weight * att)
+            }
+            _ => {
+                let coat = TrowbridgeReitz::new(CLEARCOAT_ALPHA, CLEARCOAT_ALPHA);
+                let wm = coat.sample_wm(&wo, crate::rand(), crate::rand());
+                let wi = reflect_local(&wo, &wm);
+                if wi.z <= 0.0 {
+                    return None;
+                }
+                let g = coat.g(&wo, &wi) / coat.g1(&wo);
+                let f = schlick(Color::new(0.04, 0.04, 0.04), wo.dot(&wm));
+                (wi, w_clearcoat * g * f)
+            }
+        };
+
+        Some((estimate / select, Ray::new(rec.p, rec.to_world(&wi))))
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Color {
+        self.emission
+            .value_filtered(rec.u, rec.v, &rec.p, &rec.footprint)
+    }
+}
+
+//dielectric coat over any base material, e.g. car paint or varnish. light either
+//reflects off the coat by fresnel or refracts through it to the base. what the base
+//scatters back up leaves by fresnel or reflects back down onto the base again, and is
+//attenuated by the coat's absorption over each trip through its thickness
+pub struct Coated<M: Mat> {
+    pub base: M,
+    pub ior: f64,
+    pub absorption: Color,
+    pub thickness: f64,
+    distribution: TrowbridgeReitz,
+}
+
+const MAX_COAT_BOUNCES: i32 = 16;
+
+impl<M: Mat> Coated<M> {
+    pub fn new(base: M, ior: f64, roughness: f64) -> Self {
+        let alpha = TrowbridgeReitz::roughness_to_alpha(roughness);
+        Self {
+            base,
+            ior,
+            absorption: Color::zero(),
+            thickness: 0.0,
+            distribution: TrowbridgeReitz::new(alpha, alpha),
+        }
+    }
+
+    pub fn with_absorption(mut self, absorption: Color, thickness: f64) -> Self {
+        self.absorption = absorption;
+        self.thickness = thickness;
+        self
+    }
+}
+
+impl<M: Mat> Mat for Coated<M> {
+    fn scatter(&self, r_in: Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
+        let wo = rec.to_local(&(-r_in.dir.unit()));
+        if wo.z <= 0.0 {
+            return self.base.scatter(r_in, rec);
+        }
+
+        let smooth = self.distribution.effectively_smooth();
+        let wm = if smooth {
+            Vec3::new(0.0, 0.0, 1.0)
+        } else {
+            self.distribution
+                .sample_wm(&wo, crate::rand(), crate::rand())
+        };
+        let masking = |wi: &Vec3| {
+            if smooth {
+                1.0
+            } else {
+                self.distribution.g(&wo, wi) / self.distribution.g1(&wo)
+            }
+        };
+
+        let white = Color::new(1.0, 1.0, 1.0);
+        if crate::rand() < fresnel_dielectric(wo.dot(&wm), self.ior) {
+            let wi = reflect_local(&wo, &wm);
+            if wi.z <= 0.0 {
+                return None;
+            }
+            let dir = rec.to_world(&wi);
+            let scattered = if smooth {
+                rec.specular_ray(&r_in, dir, |d| reflect(d, &rec.normal))
+            } else {
+                Ray::new(rec.p, dir)
+            };
+            return Some((masking(&wi) * white, scattered));
+        }
+
+        //refract into the coat and bounce between the base and the underside of the coat
+        let mut w_down = refract_local(&wo, &wm, self.ior)?;
+        let mut att = masking(&w_down) * white;
+        let down = Vec3::new(0.0, 0.0, -1.0);
+        for _ in 0..MAX_COAT_BOUNCES {
+            att *= beer_lambert(&self.absorption, self.thickness / -w_down.z);
+            let inner = Ray::new(rec.p, rec.to_world(&w_down));
+            let (base_att, base_out) = self.base.scatter(inner, rec)?;
+            att *= base_att;
+
+            let w_up = rec.to_local(&base_out.dir.unit());
+            if w_up.z <= 0.0 {
+                //the base transmitted the light, it never crosses the coat again
+                return Some((att, base_out));
+            }
+            att *= beer_lambert(&self.absorption, self.thickness / w_up.z);
+
+            //leave through the coat from below, the normal facing down into it
+            if crate::rand() >= fresnel_dielectric(w_up.z, 1.0 / self.ior) {
+                let w_exit = refract_local(&-w_up, &down, 1.0 / self.ior)?;
+                return Some((att, Ray::new(rec.p, rec.to_world(&w_exit))));
+            }
+            w_down = Vec3::new(w_up.x, w_up.y, -w_up.z);
+        }
+        None
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Color {
+        self.base.emitted(rec)
+    }
+
+    fn alpha(&self, rec: &HitRecord) -> f64 {
+        self.base.alpha(rec)
+    }
+}
+
+//blends two materials by a mask texture, a where the mask is 0 and b where it is 1 (e.g.
+//rust patches over metal with a Noisy mask). each hit picks one material with
+//probability equal to its blend weight, so the picked material's attenuation is already
+//the right estimate of the blend and needs no reweighting
+pub struct MixMaterial<A: Mat, B: Mat, T: Texture> {
+    pub a: A,
+    pub b: B,
+    pub mask: T,
+}
+
+impl<A: Mat, B: Mat, T: Texture> MixMaterial<A, B, T> {
+    pub fn new(a: A, b: B, mask: T) -> Self {
+        Self { a, b, mask }
+    }
+
+    fn weight(&self, rec: &HitRecord) -> f64 {
+        self.mask
+            .value_filtered(rec.u, rec.v, &rec.p, &rec.footprint)
+            .mean()
+            .clamp(0.0, 1.0)
+    }
+}
+
+impl<A: Mat, B: Mat, T: Texture> Mat for MixMaterial<A, B, T> {
+    fn scatter(&self, r_in: Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
+        if crate::rand() < self.weight(rec) {
+            self.b.scatter(r_in, rec)
+        } else {
+            self.a.scatter(r_in, rec)
+        }
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Color {
+        let m = self.weight(rec);
+        (1.0 - m) * self.a.emitted(rec) + m * self.b.emitted(rec)
+    }
+
+    fn alpha(&self, rec: &HitRecord) -> f64 {
+        let m = self.weight(rec);
+        (1.0 - m) * self.a.alpha(rec) + m * self.b.alpha(rec)
+    }
+}
+
+//masks the wrapped material with an alpha texture for leaves, fences and decals. the
+//mask is read as the mean of its channels, 0 fully cut out and 1 fully opaque
+pub struct Cutout<M: Mat, T: Texture> {
+    pub inner: M,
+    pub alpha: T,
+}
+
+impl<M: Mat, T: Texture> Cutout<M, T> {
+    pub fn new(inner: M, alpha: T) -> Self {
+        Self { inner, alpha }
+    }
+}
+
+impl<M: Mat, T: Texture> Mat for Cutout<M, T> {
+    fn scatter(&self, r_in: Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
+        self.inner.scatter(r_in, rec)
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Color {
+        self.inner.emitted(rec)
+    }
+
+    fn alpha(&self, rec: &HitRecord) -> f64 {
+        let a = self
+            .alpha
+            .value_filtered(rec.u, rec.v, &rec.p, &rec.footprint)
+            .mean();
+        a.clamp(0.0, 1.0) * self.inner.alpha(rec)
+    }
+}
+
+//phase function for participating media, scatters uniformly over the sphere of directions
+#[derive(Copy, Clone)]
+pub struct Isotropic<T: Texture> {
+    pub albedo: T,
+}
+
+impl<T: Texture> Isotropic<T> {
+    pub fn new(albedo: T) -> Self {
+        Self { albedo }
+    }
+}
+
+impl<T: Texture> Mat for Isotropic<T> {
+    fn scatter(&self, _r_in: Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
+        let dir = Vec3::rand_within_unit_sphere().unit();
+        Some((
+            self.albedo.value(rec.u, rec.v, &rec.p),
+            Ray::new(rec.p, dir),
+        ))
+    }
+}
+
+//henyey-greenstein phase function, g > 0 scatters forward and g < 0 backward. sampled
+//exactly so the weight is the albedo
+#[derive(Copy, Clone)]
+pub struct HenyeyGreenstein<T: Texture> {
+    pub albedo: T,
+    pub g: f64,
+}
+
+impl<T: Texture> HenyeyGreenstein<T> {
+    pub fn new(albedo: T, g: f64) -> Self {
+        Self {
+            albedo,
+            g: g.clamp(-0.99, 0.99),
+        }
+    }
+}
+
+//samples a direction around the propagation direction forward with density given by the
+//henyey-greenstein phase function
+pub(crate) fn sample_henyey_greenstein(forward: &Vec3, g: f64) -> Vec3 {
+    let u = crate::rand();
+    let cos_theta = if g.abs() < 1e-3 {
+        1.0 - 2.0 * u
+    } else {
+        let sq = (1.0 - g * g) / (1.0 - g + 2.0 * g * u);
+        ((1.0 + g * g - sq * sq) / (2.0 * g)).clamp(-1.0, 1.0)
+    };
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * crate::PI * crate::rand();
+    Frame::from_normal(forward.unit()).to_world(&Vec3::new(
+        sin_theta * phi.cos(),
+        sin_theta * phi.sin(),
+        cos_theta,
+    ))
+}
+
+impl<T: Texture> Mat for HenyeyGreenstein<T> {
+    fn scatter(&self, r_in: Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
+        let dir = sample_henyey_greenstein(&r_in.dir, self.g);
+        Some((
+            self.albedo.value(rec.u, rec.v, &rec.p),
+            Ray::new(rec.p, dir),
+        ))
+    }
+}
+
+//reflects or refracts the unit direction dir at an interface with normal n facing
+//against it, picked stochastically by fresnel reflectance
+fn fresnel_bounce(dir: &Vec3, n: &Vec3, refraction_ratio: f64) -> Vec3 {
+    let cos_theta = f64::min((-*dir).dot(n), 1.0);
+    let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+    if refraction_ratio * sin_theta > 1.0
+        || reflectance(cos_theta, refraction_ratio) > crate::rand()
+    {
+        reflect(dir, n)
+    } else {
+        refract(dir, n, refraction_ratio)
+    }
+}
+
+fn exp_per_channel(sigma: &Color, distance: f64) -> Color {
+    Color::new(
+        (-sigma.x * distance).exp(),
+        (-sigma.y * distance).exp(),
+        (-sigma.z * distance).exp(),
+    )
+}
+
+//subsurface scattering by a random walk through the interior of boundary, which must be a
+//closed surface. albedo is the single scattering albedo and mean_free_path the average
+//distance between scattering events, both per channel. wrap the boundary in this instead
+//of giving it a material: hits on the boundary are reported with the walk as material.
+//the walk runs in the boundary's space, so place it with an instance inside the subsurface
+//rather than around it
+pub struct Subsurface<H: Hittable> {
+    pub boundary: H,
+    pub albedo: Color,
+    pub mean_free_path: Color,
+    pub ior: f64,
+    //henyey-greenstein asymmetry of the interior scattering
+    pub g: f64,
+    //walks still inside after this many events are treated as absorbed
+    pub max_steps: u32,
+}
+
+impl<H: Hittable> Subsurface<H> {
+    pub fn new(boundary: H, albedo: Color, mean_free_path: Color, ior: f64) -> Self {
+        Self {
+            boundary,
+            albedo,
+            mean_free_path,
+            ior,
+            g: 0.0,
+            max_steps: 1024,
+        }
+    }
+
+    pub fn with_anisotropy(mut self, g: f64) -> Self {
+        self.g = g.clamp(-0.99, 0.99);
+        self
+    }
+
+    pub fn with_max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    //walks from p in unit direction dir until the path refracts out of the boundary,
+    //returning the throughput and the exit ray. distances are sampled with the extinction
+    //of one channel chosen uniformly for the whole walk, and the path is weighted by the
+    //average of its pdf under each channel
+    fn walk(&self, mut p: Point, mut dir: Vec3) -> Option<(Color, Point, Vec3)> {
+        let sigma_t = Color::new(
+            1.0 / self.mean_free_path.x.max(1e-9),
+            1.0 / self.mean_free_path.y.max(1e-9),
+            1.0 / self.mean_free_path.z.max(1e-9),
+        );
+        let sigma_s = self.albedo * sigma_t;
+        let channel = (3.0 * crate::rand()).min(2.0) as i32;
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        let mut pdf = Color::new(1.0, 1.0, 1.0);
+
+        for _ in 0..self.max_steps {
+            let exit = self.boundary.hit(&Ray::new(p, dir), 0.0001, INFINITY)?;
+            let s = -(1.0 - crate::rand()).ln() / sigma_t.get(channel);
+
+            if s < exit.t {
+                let tr = exp_per_channel(&sigma_t, s);
+                throughput *= sigma_s * tr;
+                pdf *= sigma_t * tr;
+                p += s * dir;
+                dir = sample_henyey_greenstein(&dir, self.g);
+            } else {
+                let tr = exp_per_channel(&sigma_t, exit.t);
+                throughput *= tr;
+                pdf *= tr;
+                p = exit.p;
+                let refraction_ratio = if exit.front_face {
+                    1.0 / self.ior
+                } else {
+                    self.ior
+                };
+                let bounced = fresnel_bounce(&dir, &exit.normal, refraction_ratio).unit();
+                //leaving the interior ends the walk, internal reflections carry on
+                if bounced.dot(&exit.normal) < 0.0 {
+                    return Some((throughput / pdf.mean(), p, bounced));
+                }
+                dir = bounced;
+            }
+
+            //only the ratio matters, so rescale by the sampled channel's pdf to keep long
+            //walks from underflowing
+            let scale = pdf.get(channel);
+            if scale <= 0.0 {
+                return None;
+            }
+            throughput = throughput / scale;
+            pdf = pdf / scale;
+        }
+        None
+    }
+}
+
+impl<H: Hittable> Hittable for Subsurface<H> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        //cutouts in the boundary's own material are resolved before the record is taken over
+        let mut rec = hit_opaque(&self.boundary, r, t_min, t_max)?;
+        rec.material = self;
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.boundary.bounding_box()
+    }
+}
+
+impl<H: Hittable> Mat for Subsurface<H> {
+    fn scatter(&self, r_in: Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
+        let dir = r_in.dir.unit();
+        //rays arriving from inside only pass through, the walk starts from outside
+        if !rec.front_face {
+            let mut through = Ray::new(rec.p, dir);
+            through.wavelengths = r_in.wavelengths;
+            return Some((Color::new(1.0, 1.0, 1.0), through));
+        }
+
+        let entered = fresnel_bounce(&dir, &rec.normal, 1.0 / self.ior).unit();
+        let (weight, p, out) = if entered.dot(&rec.normal) > 0.0 {
+            (Color::new(1.0, 1.0, 1.0), rec.p, entered)
+        } else {
+            self.walk(rec.p, entered)?
+        };
+        let mut scattered = Ray::new(p, out);
+        scattered.wavelengths = r_in.wavelengths;
+        Some((weight, scattered))
+    }
+}
+
+//area light that emits from its front face and absorbs everything arriving at it
+#[derive(Copy, Clone)]
+pub struct DiffuseLight<T: Texture> {
+    pub emit: T,
+}
+
+impl<T: Texture> DiffuseLight<T> {
+    pub fn new(emit: T) -> Self {
+        Self { emit }
+    }
+}
+
+impl<T: Texture> Mat for DiffuseLight<T> {
+    fn scatter(&self, _r_in: Ray, _rec: &HitRecord) -> Option<(Color, Ray)> {
+        None
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Color {
+        if rec.front_face {
+            self.emit.value(rec.u, rec.v, &rec.p)
+        } else {
+            Color::zero()
+        }
+    }
+}
@@ -0,0 +1,150 @@
+//module for participating media such as smoke and fog bounded by another hittable
+use crate::hittable::{HitRecord, Hittable};
+use crate::materials::Mat;
+use crate::texture::Texture;
+use crate::{Point, Ray, Vec3, AABB, INFINITY};
+
+//homogeneous medium filling a closed boundary. rays scatter after an exponentially
+//distributed free flight, so thin regions are mostly passed through
+pub struct ConstantMedium<H: Hittable, M: Mat> {
+    pub boundary: H,
+    pub density: f64,
+    pub phase: M,
+}
+
+impl<H: Hittable, M: Mat> ConstantMedium<H, M> {
+    pub fn new(boundary: H, density: f64, phase: M) -> Self {
+        Self {
+            boundary,
+            density,
+            phase,
+        }
+    }
+}
+
+impl<H: Hittable, M: Mat> Hittable for ConstantMedium<H, M> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        //entry and exit of the boundary along the whole line, so rays starting inside
+        //the medium are handled too
+        let enter = self.boundary.hit(r, -INFINITY, INFINITY)?;
+        let exit = self.boundary.hit(r, enter.t + 0.0001, INFINITY)?;
+
+        let t_enter = enter.t.max(t_min).max(0.0);
+        let t_exit = exit.t.min(t_max);
+        if t_enter >= t_exit {
+            return None;
+        }
+
+        let ray_length = r.dir.length();
+        let distance_inside = (t_exit - t_enter) * ray_length;
+        let hit_distance = -(1.0 - crate::rand()).ln() / self.density;
+        if hit_distance > distance_inside {
+            return None;
+        }
+
+        let t = t_enter + hit_distance / ray_length;
+        let p = r.at(t);
+        //normal is arbitrary inside a medium, the phase function ignores it
+        Some(HitRecord::new(
+            p,
+            t,
+            Vec3::new(1.0, 0.0, 0.0),
+            r,
+            &self.phase,
+            (enter.u, enter.v),
+        ))
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.boundary.bounding_box()
+    }
+}
+
+//medium whose density varies through space, read as the mean of a 3d texture times
+//scale. majorant must be positive and bound the scaled density everywhere inside the
+//boundary, denser regions are silently thinned out to the majorant
+pub struct HeterogeneousMedium<H: Hittable, D: Texture, M: Mat> {
+    pub boundary: H,
+    pub density: D,
+    pub scale: f64,
+    pub majorant: f64,
+    pub phase: M,
+}
+
+impl<H: Hittable, D: Texture, M: Mat> HeterogeneousMedium<H, D, M> {
+    //panics if majorant is not positive
+    pub fn new(boundary: H, density: D, scale: f64, majorant: f64, phase: M) -> Self {
+        assert!(majorant > 0.0, "majorant must be positive, got {majorant}");
+        Self {
+            boundary,
+            density,
+            scale,
+            majorant,
+            phase,
+        }
+    }
+
+    fn density_at(&self, p: &Point) -> f64 {
+        (self.scale * self.density.value(0.0, 0.0, p).mean()).max(0.0)
+    }
+
+    //parametric span of the ray inside the boundary, clipped to [t_min, t_max]
+    fn span(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+        let enter = self.boundary.hit(r, -INFINITY, INFINITY)?;
+        let exit = self.boundary.hit(r, enter.t + 0.0001, INFINITY)?;
+        let t_enter = enter.t.max(t_min).max(0.0);
+        let t_exit = exit.t.min(t_max);
+        (t_enter < t_exit).then_some((t_enter, t_exit))
+    }
+
+    //fraction of light surviving between t_min and t_max, estimated with ratio tracking
+    pub fn transmittance(&self, r: &Ray, t_min: f64, t_max: f64) -> f64 {
+        let Some((t_enter, t_exit)) = self.span(r, t_min, t_max) else {
+            return 1.0;
+        };
+        let ray_length = r.dir.length();
+        let mut t = t_enter;
+        let mut tr = 1.0;
+        loop {
+            t -= (1.0 - crate::rand()).ln() / (self.majorant * ray_length);
+            if t >= t_exit {
+                return tr;
+            }
+            tr *= 1.0 - (self.density_at(&r.at(t)) / self.majorant).min(1.0);
+            if tr <= 0.0 {
+                return 0.0;
+            }
+        }
+    }
+}
+
+impl<H: Hittable, D: Texture, M: Mat> Hittable for HeterogeneousMedium<H, D, M> {
+    //delta tracking: tentative collisions are sampled against the majorant and accepted
+    //as real with probability density / majorant, the rest are null collisions
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let (t_enter, t_exit) = self.span(r, t_min, t_max)?;
+        let ray_length = r.dir.length();
+        let mut t = t_enter;
+        loop {
+            t -= (1.0 - crate::rand()).ln() / (self.majorant * ray_length);
+            if t >= t_exit {
+                return None;
+            }
+            let p = r.at(t);
+            if crate::rand() * self.majorant < self.density_at(&p) {
+                return Some(HitRecord::new(
+                    p,
+                    t,
+                    Vec3::new(1.0, 0.0, 0.0),
+                    r,
+                    &self.phase,
+                    (0.0, 0.0),
+                ));
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.boundary.bounding_box()
+    }
+}
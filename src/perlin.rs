@@ -1,13 +1,10 @@
-use crate::Point;
+use crate::{Point, Vec3};
 use rand::Rng;
 
-fn gen_perlin(len: usize) -> Vec<f64> {
-    let mut rng = rand::thread_rng();
-    let mut p = Vec::with_capacity(256);
-    for _ in 0..len {
-        p.push(rng.gen());
-    }
-    p
+const N_POINTS: usize = 256;
+
+fn gen_perlin(len: usize) -> Vec<Vec3> {
+    (0..len).map(|_| Vec3::rand(-1.0, 1.0).unit()).collect()
 }
 
 fn gen_perlin_perm(len: usize) -> Vec<usize> {
@@ -16,51 +13,125 @@ fn gen_perlin_perm(len: usize) -> Vec<usize> {
     v
 }
 
-fn permute(v: &mut Vec<usize>) {
+fn permute(v: &mut [usize]) {
     let n = v.len();
     let mut rng = rand::thread_rng();
-    for i in (0..n as usize).rev() {
+    for i in (0..n).rev() {
         let target = rng.gen_range(0..=i);
         v.swap(i, target);
     }
 }
 
+//hermite cubic, smooths the interpolation weights so the noise has no grid artifacts
+fn hermite(t: f64) -> f64 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn trilinear_interp(c: &[[[Vec3; 2]; 2]; 2], u: f64, v: f64, w: f64) -> f64 {
+    let uu = hermite(u);
+    let vv = hermite(v);
+    let ww = hermite(w);
+
+    let mut acc = 0.0;
+    for (i, plane) in c.iter().enumerate() {
+        for (j, row) in plane.iter().enumerate() {
+            for (k, gradient) in row.iter().enumerate() {
+                let (fi, fj, fk) = (i as f64, j as f64, k as f64);
+                let weight = Vec3::new(u - fi, v - fj, w - fk);
+                acc += (fi * uu + (1.0 - fi) * (1.0 - uu))
+                    * (fj * vv + (1.0 - fj) * (1.0 - vv))
+                    * (fk * ww + (1.0 - fk) * (1.0 - ww))
+                    * gradient.dot(&weight);
+            }
+        }
+    }
+    acc
+}
+
 #[derive(Clone)]
 pub struct Perlin {
-    ranfloat: Vec<f64>,
+    ranvec: Vec<Vec3>,
     perm_x: Vec<usize>,
     perm_y: Vec<usize>,
     perm_z: Vec<usize>,
+    pub frequency: f64,
+}
+
+impl Default for Perlin {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Perlin {
     pub fn new() -> Self {
-        let n_points = 256;
+        Self::with_frequency(1.0)
+    }
+
+    pub fn with_frequency(frequency: f64) -> Self {
         Self {
-            ranfloat: gen_perlin(n_points),
-            perm_x: gen_perlin_perm(n_points),
-            perm_y: gen_perlin_perm(n_points),
-            perm_z: gen_perlin_perm(n_points),
+            ranvec: gen_perlin(N_POINTS),
+            perm_x: gen_perlin_perm(N_POINTS),
+            perm_y: gen_perlin_perm(N_POINTS),
+            perm_z: gen_perlin_perm(N_POINTS),
+            frequency,
         }
     }
 
+    //gradient noise in roughly [-1, 1]
     pub fn noise(&self, p: &Point) -> f64 {
+        let p = self.frequency * *p;
         let u = p.x - p.x.floor();
         let v = p.y - p.y.floor();
         let w = p.z - p.z.floor();
 
-        let i = (4.0 * p.x).floor() as usize;
-        let j = (4.0 * p.y).floor() as usize;
-        let k = (4.0 * p.z).floor() as usize;
-        
-        for i in 0..2 {
-            for j in 0..2 {
-                for k in 0..2 {
+        //floor to i64 before masking so negative coordinates wrap instead of underflowing
+        let i = p.x.floor() as i64;
+        let j = p.y.floor() as i64;
+        let k = p.z.floor() as i64;
 
+        let mut c = [[[Vec3::zero(); 2]; 2]; 2];
+        for (di, plane) in c.iter_mut().enumerate() {
+            for (dj, row) in plane.iter_mut().enumerate() {
+                for (dk, gradient) in row.iter_mut().enumerate() {
+                    *gradient = self.ranvec[self.perm_x[((i + di as i64) & 255) as usize]
+                        ^ self.perm_y[((j + dj as i64) & 255) as usize]
+                        ^ self.perm_z[((k + dk as i64) & 255) as usize]];
                 }
             }
         }
 
-        self.ranfloat[self.perm_x[i as usize] ^ self.perm_y[j as usize] ^ self.perm_z[k as usize]]
+        trilinear_interp(&c, u, v, w)
+    }
+
+    //fractional brownian motion: sums octaves of noise, each scaled in frequency by
+    //lacunarity and in amplitude by gain
+    pub fn fbm(&self, p: &Point, octaves: u32, lacunarity: f64, gain: f64) -> f64 {
+        let mut acc = 0.0;
+        let mut temp_p = *p;
+        let mut weight = 1.0;
+        for _ in 0..octaves {
+            acc += weight * self.noise(&temp_p);
+            weight *= gain;
+            temp_p = temp_p * lacunarity;
+        }
+        acc
+    }
+
+    //same as fbm but sums the absolute value of each octave
+    pub fn turbulence(&self, p: &Point, octaves: u32, lacunarity: f64, gain: f64) -> f64 {
+        let mut acc = 0.0;
+        let mut temp_p = *p;
+        let mut weight = 1.0;
+        for _ in 0..octaves {
+            acc += weight * self.noise(&temp_p).abs();
+            weight *= gain;
+            temp_p = temp_p * lacunarity;
+        }
+        acc
+    }
+
+    pub fn turb(&self, p: &Point, octaves: u32) -> f64 {
+        self.turbulence(p, octaves, 2.0, 0.5)
     }
 }
@@ -0,0 +1,133 @@
+//module for infinite planes and disks
+use crate::frame::{azimuth, disk_extent};
+use crate::hittable::{HitRecord, Hittable, Periodic};
+use crate::materials::*;
+use crate::{Frame, Point, Ray, Vec3, AABB, PI};
+
+//infinite plane through point. uvs are distances along the plane's tangents divided by
+//uv_scale, so textures tile every uv_scale units
+pub struct Plane<M: Mat> {
+    pub point: Point,
+    pub material: M,
+    pub uv_scale: f64,
+    frame: Frame,
+}
+
+impl<M: Mat> Plane<M> {
+    pub fn new(point: Point, normal: Vec3, material: M) -> Self {
+        Self {
+            point,
+            material,
+            uv_scale: 1.0,
+            frame: Frame::from_normal(normal.unit()),
+        }
+    }
+
+    pub fn with_uv_scale(mut self, uv_scale: f64) -> Self {
+        self.uv_scale = uv_scale;
+        self
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        self.frame.n
+    }
+
+    fn get_uv(&self, p: &Point) -> (f64, f64) {
+        let local = self.frame.to_local(&(*p - self.point));
+        (local.x / self.uv_scale, local.y / self.uv_scale)
+    }
+}
+
+impl<M: Mat> Hittable for Plane<M> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let denom = self.frame.n.dot(&r.dir);
+        if denom.abs() < 1e-12 {
+            return None;
+        }
+        let t = self.frame.n.dot(&(self.point - r.origin)) / denom;
+        if t < t_min || t > t_max {
+            return None;
+        }
+
+        let p = r.at(t);
+        Some(
+            HitRecord::new(p, t, self.frame.n, r, &self.material, self.get_uv(&p))
+                .with_tangents(self.uv_scale * self.frame.s, self.uv_scale * self.frame.t)
+                .with_differentials(r, &|q: &Point| self.get_uv(q), Periodic::NONE),
+        )
+    }
+
+    //planes are unbounded, the bvh keeps them out of its hierarchy
+    fn bounding_box(&self) -> AABB {
+        AABB::unbounded()
+    }
+}
+
+//flat disk facing along normal. u is the angle around the center and v the distance
+//from it, both scaled to [0, 1]
+pub struct Disk<M: Mat> {
+    pub center: Point,
+    pub radius: f64,
+    pub material: M,
+    frame: Frame,
+}
+
+impl<M: Mat> Disk<M> {
+    pub fn new(center: Point, normal: Vec3, radius: f64, material: M) -> Self {
+        Self {
+            center,
+            radius,
+            material,
+            frame: Frame::from_normal(normal.unit()),
+        }
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        self.frame.n
+    }
+
+    fn get_uv(&self, p: &Point) -> (f64, f64) {
+        let local = self.frame.to_local(&(*p - self.center));
+        (
+            azimuth(&local) / (2.0 * PI),
+            (local.x * local.x + local.y * local.y).sqrt() / self.radius,
+        )
+    }
+}
+
+impl<M: Mat> Hittable for Disk<M> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let denom = self.frame.n.dot(&r.dir);
+        if denom.abs() < 1e-12 {
+            return None;
+        }
+        let t = self.frame.n.dot(&(self.center - r.origin)) / denom;
+        if t < t_min || t > t_max {
+            return None;
+        }
+
+        let p = r.at(t);
+        if (p - self.center).l2() > self.radius * self.radius {
+            return None;
+        }
+
+        let (u, v) = self.get_uv(&p);
+        let (sin_phi, cos_phi) = (2.0 * PI * u).sin_cos();
+        let radial = cos_phi * self.frame.s + sin_phi * self.frame.t;
+        let around = -sin_phi * self.frame.s + cos_phi * self.frame.t;
+        Some(
+            HitRecord::new(p, t, self.frame.n, r, &self.material, (u, v))
+                .with_tangents(2.0 * PI * v * self.radius * around, self.radius * radial)
+                .with_differentials(r, &|q: &Point| self.get_uv(q), Periodic::U),
+        )
+    }
+
+    fn bounding_box(&self) -> AABB {
+        let extent = disk_extent(&self.frame.n, self.radius);
+        AABB {
+            min: self.center - extent,
+            max: self.center + extent,
+        }
+        .pad(0.0001)
+    }
+}
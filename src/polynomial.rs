@@ -0,0 +1,189 @@
+//module for the real roots of low degree polynomials, used by the curved primitives
+
+//roots of a t^2 + 2 half_b t + c in increasing order, the same form the sphere uses
+pub fn solve_quadratic(a: f64, half_b: f64, c: f64) -> Option<(f64, f64)> {
+    if a.abs() < 1e-12 {
+        if half_b.abs() < 1e-12 {
+            return None;
+        }
+        let t = -c / (2.0 * half_b);
+        return Some((t, t));
+    }
+    let discriminant = half_b * half_b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    //avoids cancellation between -half_b and the square root
+    let q = -(half_b + half_b.signum() * discriminant.sqrt());
+    if q == 0.0 {
+        return Some((0.0, 0.0));
+    }
+    let (t0, t1) = (q / a, c / q);
+    Some((t0.min(t1), t0.max(t1)))
+}
+
+//largest real root of x^3 + a x^2 + b x + c
+fn largest_cubic_root(a: f64, b: f64, c: f64) -> f64 {
+    let q = (a * a - 3.0 * b) / 9.0;
+    let r = (2.0 * a * a * a - 9.0 * a * b + 27.0 * c) / 54.0;
+    let root = if r * r < q * q * q {
+        let theta = (r / (q * q * q).sqrt()).clamp(-1.0, 1.0).acos();
+        let s = -2.0 * q.sqrt();
+        (0..3)
+            .map(|k| s * ((theta + 2.0 * crate::PI * k as f64) / 3.0).cos() - a / 3.0)
+            .fold(f64::MIN, f64::max)
+    } else {
+        let big_a = -r.signum() * (r.abs() + (r * r - q * q * q).sqrt()).cbrt();
+        let big_b = if big_a == 0.0 { 0.0 } else { q / big_a };
+        big_a + big_b - a / 3.0
+    };
+    polish(&[1.0, a, b, c], root)
+}
+
+fn eval(coeffs: &[f64], x: f64) -> (f64, f64) {
+    let mut f = 0.0;
+    let mut df = 0.0;
+    for c in coeffs {
+        df = df * x + f;
+        f = f * x + c;
+    }
+    (f, df)
+}
+
+//a few newton steps to recover the precision lost in the closed form solutions
+fn polish(coeffs: &[f64], mut x: f64) -> f64 {
+    for _ in 0..4 {
+        let (f, df) = eval(coeffs, x);
+        if df.abs() < 1e-300 {
+            break;
+        }
+        let next = x - f / df;
+        if !next.is_finite() {
+            break;
+        }
+        x = next;
+    }
+    x
+}
+
+//real roots of c[0] x^4 + c[1] x^3 + c[2] x^2 + c[3] x + c[4] in increasing order, found
+//with ferrari's method on the depressed quartic
+pub fn solve_quartic(c: [f64; 5]) -> Vec<f64> {
+    let a = c[1] / c[0];
+    let b = c[2] / c[0];
+    let cc = c[3] / c[0];
+    let d = c[4] / c[0];
+
+    //substituting x = y - a / 4 gives y^4 + p y^2 + q y + r
+    let a2 = a * a;
+    let p = b - 3.0 * a2 / 8.0;
+    let q = cc - a * b / 2.0 + a2 * a / 8.0;
+    let r = d - a * cc / 4.0 + a2 * b / 16.0 - 3.0 * a2 * a2 / 256.0;
+
+    let mut ys = Vec::with_capacity(4);
+    let mut push_quadratic = |b: f64, c: f64| {
+        let disc = b * b / 4.0 - c;
+        if disc >= 0.0 {
+            let s = disc.sqrt();
+            ys.push(-b / 2.0 - s);
+            ys.push(-b / 2.0 + s);
+        }
+    };
+
+    if q.abs() < 1e-12 {
+        //biquadratic, solve for y^2
+        let disc = p * p / 4.0 - r;
+        if disc >= 0.0 {
+            for z in [-p / 2.0 - disc.sqrt(), -p / 2.0 + disc.sqrt()] {
+                if z >= 0.0 {
+                    push_quadratic(0.0, -z);
+                }
+            }
+        }
+    } else {
+        //resolvent cubic m^3 + p m^2 + (p^2 / 4 - r) m - q^2 / 8 has a positive root, which
+        //splits the quartic into two quadratics
+        let m = largest_cubic_root(p, p * p / 4.0 - r, -q * q / 8.0);
+        if m > 0.0 {
+            let s = (2.0 * m).sqrt();
+            push_quadratic(-s, p / 2.0 + m + q / (2.0 * s));
+            push_quadratic(s, p / 2.0 + m - q / (2.0 * s));
+        }
+    }
+
+    let mut roots: Vec<f64> = ys.into_iter().map(|y| polish(&c, y - a / 4.0)).collect();
+    roots.sort_by(|x, y| x.total_cmp(y));
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_roots(found: &[f64], expected: &[f64]) {
+        assert_eq!(found.len(), expected.len(), "{found:?} vs {expected:?}");
+        for (f, e) in found.iter().zip(expected) {
+            assert!((f - e).abs() < 1e-9, "{found:?} vs {expected:?}");
+        }
+    }
+
+    #[test]
+    fn quadratic_roots() {
+        //(t - 1)(t - 3) = t^2 - 4t + 3
+        let (t0, t1) = solve_quadratic(1.0, -2.0, 3.0).unwrap();
+        assert_roots(&[t0, t1], &[1.0, 3.0]);
+        assert!(solve_quadratic(1.0, 0.0, 1.0).is_none());
+    }
+
+    #[test]
+    fn cubic_largest_root() {
+        //(x + 2)(x - 1)(x - 4) = x^3 - 3x^2 - 6x + 8
+        assert!((largest_cubic_root(-3.0, -6.0, 8.0) - 4.0).abs() < 1e-9);
+        //one real root, (x - 2)(x^2 + 1) = x^3 - 2x^2 + x - 2
+        assert!((largest_cubic_root(-2.0, 1.0, -2.0) - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn quartic_four_roots() {
+        //(x + 1)(x - 1)(x - 2)(x - 3) = x^4 - 5x^3 + 5x^2 + 5x - 6
+        assert_roots(
+            &solve_quartic([1.0, -5.0, 5.0, 5.0, -6.0]),
+            &[-1.0, 1.0, 2.0, 3.0],
+        );
+        //scaled leading coefficient gives the same roots
+        assert_roots(
+            &solve_quartic([2.0, -10.0, 10.0, 10.0, -12.0]),
+            &[-1.0, 1.0, 2.0, 3.0],
+        );
+    }
+
+    #[test]
+    fn quartic_biquadratic() {
+        //(x^2 - 1)(x^2 - 4) has q = 0 after depressing
+        assert_roots(
+            &solve_quartic([1.0, 0.0, -5.0, 0.0, 4.0]),
+            &[-2.0, -1.0, 1.0, 2.0],
+        );
+        //(x^2 + 1)(x^2 - 9), only the real pair survives
+        assert_roots(&solve_quartic([1.0, 0.0, -8.0, 0.0, -9.0]), &[-3.0, 3.0]);
+    }
+
+    #[test]
+    fn quartic_near_zero_q() {
+        //roots symmetric about 1 depress to q = 0 only up to rounding
+        let roots = [-1.0, 0.5, 1.5, 3.0];
+        let mut c = [1.0, 0.0, 0.0, 0.0, 0.0];
+        for r in roots {
+            for i in (1..5).rev() {
+                c[i] -= r * c[i - 1];
+            }
+        }
+        assert_roots(&solve_quartic(c), &roots);
+    }
+
+    #[test]
+    fn quartic_no_real_roots() {
+        //(x^2 + 1)(x^2 + 4)
+        assert!(solve_quartic([1.0, 0.0, 5.0, 0.0, 4.0]).is_empty());
+    }
+}
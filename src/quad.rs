@@ -0,0 +1,166 @@
+//module for flat parallelograms and the boxes built from them
+use crate::hittable::{HitRecord, Hittable, HittableList, Periodic};
+use crate::materials::*;
+use crate::{Point, Ray, Vec3, AABB};
+
+//parallelogram spanned by the edges u and v from corner q. uv coordinates run from 0 to 1
+//along each edge. quads with zero length or parallel edges have no area and are never hit
+pub struct Quad<M: Mat> {
+    pub q: Point,
+    pub u: Vec3,
+    pub v: Vec3,
+    pub material: M,
+    normal: Vec3,
+    d: f64,
+    //scaled normal used to project hit points onto the edges
+    w: Vec3,
+}
+
+impl<M: Mat> Quad<M> {
+    pub fn new(q: Point, u: Vec3, v: Vec3, material: M) -> Self {
+        let n = u.cross(&v);
+        //a zero normal makes every ray look parallel to the plane, so hit always misses
+        let (normal, w) = if n.l2() <= 1e-16 * u.l2() * v.l2() {
+            (Vec3::zero(), Vec3::zero())
+        } else {
+            (n.unit(), n / n.l2())
+        };
+        Self {
+            q,
+            u,
+            v,
+            material,
+            normal,
+            d: normal.dot(&q),
+            w,
+        }
+    }
+
+    //edge coordinates of a point on the plane of the quad
+    fn get_uv(&self, p: &Point) -> (f64, f64) {
+        let planar = *p - self.q;
+        (
+            self.w.dot(&planar.cross(&self.v)),
+            self.w.dot(&self.u.cross(&planar)),
+        )
+    }
+}
+
+impl<M: Mat> Hittable for Quad<M> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let denom = self.normal.dot(&r.dir);
+        //ray is parallel to the plane
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = (self.d - self.normal.dot(&r.origin)) / denom;
+        if t < t_min || t > t_max {
+            return None;
+        }
+
+        let p = r.at(t);
+        let (a, b) = self.get_uv(&p);
+        if !(0.0..=1.0).contains(&a) || !(0.0..=1.0).contains(&b) {
+            return None;
+        }
+
+        Some(
+            HitRecord::new(p, t, self.normal, r, &self.material, (a, b))
+                .with_tangents(self.u, self.v)
+                .with_differentials(r, &|q: &Point| self.get_uv(q), Periodic::NONE),
+        )
+    }
+
+    fn bounding_box(&self) -> AABB {
+        let corners = [
+            self.q,
+            self.q + self.u,
+            self.q + self.v,
+            self.q + self.u + self.v,
+        ];
+        let mut bb = AABB {
+            min: corners[0],
+            max: corners[0],
+        };
+        for c in corners[1..].iter() {
+            bb.expand(&AABB { min: *c, max: *c });
+        }
+        //flat quads have zero thickness along at least one axis
+        bb.pad(0.0001)
+    }
+}
+
+//the six faces of the axis aligned box with opposite corners a and b, normals facing out
+pub fn make_box<M: Mat + Clone + Send + 'static>(a: Point, b: Point, material: M) -> HittableList {
+    let min = Vec3::min(&a, &b);
+    let max = Vec3::max(&a, &b);
+
+    let dx = Vec3::new(max.x - min.x, 0.0, 0.0);
+    let dy = Vec3::new(0.0, max.y - min.y, 0.0);
+    let dz = Vec3::new(0.0, 0.0, max.z - min.z);
+
+    let mut sides = HittableList::default();
+    //front, right, back, left, top, bottom
+    sides.add(Quad::new(
+        Point::new(min.x, min.y, max.z),
+        dx,
+        dy,
+        material.clone(),
+    ));
+    sides.add(Quad::new(
+        Point::new(max.x, min.y, max.z),
+        -dz,
+        dy,
+        material.clone(),
+    ));
+    sides.add(Quad::new(
+        Point::new(max.x, min.y, min.z),
+        -dx,
+        dy,
+        material.clone(),
+    ));
+    sides.add(Quad::new(min, dz, dy, material.clone()));
+    sides.add(Quad::new(
+        Point::new(min.x, max.y, max.z),
+        dx,
+        -dz,
+        material.clone(),
+    ));
+    sides.add(Quad::new(min, dx, dz, material));
+    sides
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::texture::Solid;
+    use crate::INFINITY;
+
+    fn quad(u: Vec3, v: Vec3) -> Quad<Lambertian<Solid>> {
+        let material = Lambertian::new(Solid::new(0.5, 0.5, 0.5));
+        Quad::new(Point::new(-1.0, -1.0, 0.0), u, v, material)
+    }
+
+    #[test]
+    fn hits_inside_edges() {
+        let q = quad(Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 2.0, 0.0));
+        let r = Ray::new(Point::new(0.5, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        let rec = q.hit(&r, 0.0, INFINITY).unwrap();
+        assert!((rec.t - 1.0).abs() < 1e-12);
+        assert!((rec.u - 0.75).abs() < 1e-12 && (rec.v - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn degenerate_edges_never_hit() {
+        let r = Ray::new(Point::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        for (u, v) in [
+            (Vec3::new(2.0, 0.0, 0.0), Vec3::new(4.0, 0.0, 0.0)),
+            (Vec3::new(2.0, 0.0, 0.0), Vec3::zero()),
+        ] {
+            let q = quad(u, v);
+            assert!(!q.normal.x.is_nan());
+            assert!(q.hit(&r, 0.0, INFINITY).is_none());
+        }
+    }
+}
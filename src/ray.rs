@@ -1,14 +1,43 @@
-use crate::{Point, Vec3};
+use crate::{Point, SampledWavelengths, Vec3};
+
+//offset rays one pixel over in x and y, used to estimate texture footprints
+#[derive(Debug, Clone, Copy)]
+pub struct RayDifferential {
+    pub rx_origin: Point,
+    pub rx_dir: Vec3,
+    pub ry_origin: Point,
+    pub ry_dir: Vec3,
+}
+
 #[derive(Debug)]
 pub struct Ray {
     pub origin: Point,
     pub dir: Vec3,
+    pub differential: Option<RayDifferential>,
+    //wavelengths carried by the path, none for rays that are plain rgb
+    pub wavelengths: Option<SampledWavelengths>,
 }
 
 impl Ray {
     pub fn new(origin: Point, dir: Vec3) -> Self {
-        Self { origin, dir }
+        Self {
+            origin,
+            dir,
+            differential: None,
+            wavelengths: None,
+        }
+    }
+
+    pub fn with_differential(mut self, differential: RayDifferential) -> Self {
+        self.differential = Some(differential);
+        self
+    }
+
+    pub fn with_wavelengths(mut self, wavelengths: SampledWavelengths) -> Self {
+        self.wavelengths = Some(wavelengths);
+        self
     }
+
     pub fn at(&self, t: f64) -> Point {
         self.origin + self.dir * t
     }
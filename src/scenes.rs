@@ -1,4 +1,5 @@
 use crate::*;
+use std::sync::Arc;
 
 pub fn random_scene() -> (Camera, HittableList) {
     let look_from = Point::new(13.0, 2.0, 3.0);
@@ -21,15 +22,19 @@ pub fn random_scene() -> (Camera, HittableList) {
 
     fn random_world() -> HittableList {
         let mut world = HittableList::default();
-        let ground_texture = texture::Checkered::new(
+        //the ground plane sits at y = 0 where the solid checker's sign is noise, so check
+        //in the plane's uvs instead, three squares per unit like the old sphere's pattern
+        let ground_texture = texture::UvChecker::new(
             texture::Solid::new(0.2, 0.3, 0.1),
             texture::Solid::new(0.9, 0.9, 0.9),
+            3.0,
+            3.0,
         );
         let ground_mat = materials::Lambertian::new(ground_texture);
 
-        world.add(Sphere::new(
-            Point::new(0.0, -1000.0, -1.0),
-            1000.0,
+        world.add(Plane::new(
+            Point::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
             ground_mat,
         ));
 
@@ -133,9 +138,9 @@ pub fn two_perlin_spheres() -> (Camera, HittableList) {
     let pertext = texture::Noisy::new();
     let ground_mat = materials::Lambertian::new(pertext);
 
-    world.add(Sphere::new(
-        Point::new(0.0, -1000.0, -1.0),
-        1000.0,
+    world.add(Plane::new(
+        Point::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
         ground_mat.clone(),
     ));
 
@@ -147,3 +152,162 @@ pub fn two_perlin_spheres() -> (Camera, HittableList) {
 
     (camera, world)
 }
+
+pub fn cornell_box() -> (Camera, HittableList) {
+    let look_from = Point::new(278.0, 278.0, -800.0);
+    let look_at = Point::new(278.0, 278.0, 0.0);
+    let v_up = Point::new(0.0, 1.0, 0.0);
+    let dist_to_focus = 10.0;
+    let aperture = 0.0;
+
+    let camera = Camera::new(
+        40.0,
+        1.0,
+        &look_from,
+        &look_at,
+        &v_up,
+        dist_to_focus,
+        aperture,
+    );
+
+    let mut world = HittableList::default();
+
+    let red = materials::Lambertian::new(texture::Solid::new(0.65, 0.05, 0.05));
+    let white = materials::Lambertian::new(texture::Solid::new(0.73, 0.73, 0.73));
+    let green = materials::Lambertian::new(texture::Solid::new(0.12, 0.45, 0.15));
+    let light = materials::DiffuseLight::new(texture::Solid::new(15.0, 15.0, 15.0));
+
+    world.add(Quad::new(
+        Point::new(555.0, 0.0, 0.0),
+        Vec3::new(0.0, 555.0, 0.0),
+        Vec3::new(0.0, 0.0, 555.0),
+        green,
+    ));
+    world.add(Quad::new(
+        Point::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 555.0, 0.0),
+        Vec3::new(0.0, 0.0, 555.0),
+        red,
+    ));
+    //light faces down into the box
+    world.add(Quad::new(
+        Point::new(343.0, 554.0, 332.0),
+        Vec3::new(-130.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, -105.0),
+        light,
+    ));
+    world.add(Quad::new(
+        Point::new(0.0, 0.0, 0.0),
+        Vec3::new(555.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 555.0),
+        white.clone(),
+    ));
+    world.add(Quad::new(
+        Point::new(555.0, 555.0, 555.0),
+        Vec3::new(-555.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, -555.0),
+        white.clone(),
+    ));
+    world.add(Quad::new(
+        Point::new(0.0, 0.0, 555.0),
+        Vec3::new(555.0, 0.0, 0.0),
+        Vec3::new(0.0, 555.0, 0.0),
+        white.clone(),
+    ));
+
+    world.add(
+        Instance::new(
+            make_box(
+                Point::new(0.0, 0.0, 0.0),
+                Point::new(165.0, 330.0, 165.0),
+                white.clone(),
+            ),
+            Transform::rotate_y(15.0),
+        )
+        .translate(Vec3::new(265.0, 0.0, 295.0)),
+    );
+    world.add(
+        Instance::new(
+            make_box(
+                Point::new(0.0, 0.0, 0.0),
+                Point::new(165.0, 165.0, 165.0),
+                white,
+            ),
+            Transform::rotate_y(-18.0),
+        )
+        .translate(Vec3::new(130.0, 0.0, 65.0)),
+    );
+
+    (camera, world)
+}
+
+//a thousand copies of one tree. the tree is built once with its own bvh and shared by
+//every instance, so building a bvh over the returned list gives a two level hierarchy
+pub fn forest() -> (Camera, HittableList) {
+    let look_from = Point::new(0.0, 6.0, -24.0);
+    let look_at = Point::new(0.0, 0.0, 0.0);
+    let v_up = Point::new(0.0, 1.0, 0.0);
+    let dist_to_focus = 24.0;
+    let aperture = 0.0;
+
+    let camera = Camera::new(
+        40.0,
+        3.0 / 2.0,
+        &look_from,
+        &look_at,
+        &v_up,
+        dist_to_focus,
+        aperture,
+    );
+
+    let bark = materials::Lambertian::new(texture::Solid::new(0.35, 0.22, 0.12));
+    let leaves = materials::Lambertian::new(texture::Solid::new(0.1, 0.4, 0.12));
+    let mut parts = HittableList::default();
+    parts.add(Cylinder::new(
+        Point::new(0.0, 0.0, 0.0),
+        Point::new(0.0, 0.8, 0.0),
+        0.1,
+        bark,
+    ));
+    parts.add(Cone::new(
+        Point::new(0.0, 0.5, 0.0),
+        Point::new(0.0, 1.7, 0.0),
+        0.6,
+        leaves.clone(),
+    ));
+    parts.add(Cone::new(
+        Point::new(0.0, 1.2, 0.0),
+        Point::new(0.0, 2.3, 0.0),
+        0.45,
+        leaves,
+    ));
+    //bottom level, one bvh over the parts of a tree shared by every instance
+    let tree: Arc<dyn Hittable + Send> = Arc::new(BVH::from_hittable_list(parts));
+
+    let mut trees = HittableList::default();
+    for a in -16..16 {
+        for b in -16..16 {
+            let position = Vec3::new(
+                a as f64 + rand_range(-0.3, 0.3),
+                0.0,
+                b as f64 + rand_range(-0.3, 0.3),
+            );
+            trees.add(
+                Instance::new(tree.clone(), Transform::uniform_scale(rand_range(0.6, 1.3)))
+                    .rotate(v_up, rand_range(0.0, 360.0))
+                    .translate(1.5 * position),
+            );
+        }
+    }
+
+    //top level, a bvh over the instances
+    let mut world = HittableList::default();
+    world.add(BVH::from_hittable_list(trees));
+    world.add(Plane::new(
+        Point::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        materials::Lambertian::new(texture::Solid::new(0.4, 0.35, 0.2)),
+    ));
+
+    (camera, world)
+}
@@ -0,0 +1,214 @@
+//module for wavelength sampling and spectral to rgb conversion
+use crate::{Color, Vec3};
+use std::sync::OnceLock;
+
+const N_SAMPLES: usize = 8;
+
+pub const LAMBDA_MIN: f64 = 360.0;
+pub const LAMBDA_MAX: f64 = 830.0;
+
+//piecewise gaussian used by the cie fit
+fn lobe(x: f64, mu: f64, sigma_lo: f64, sigma_hi: f64) -> f64 {
+    let sigma = if x < mu { sigma_lo } else { sigma_hi };
+    let t = (x - mu) / sigma;
+    (-0.5 * t * t).exp()
+}
+
+//cie 1931 color matching functions, multi-lobe fit from Wyman et al. 2013
+pub fn cie_xyz(lambda: f64) -> Vec3 {
+    Vec3::new(
+        1.056 * lobe(lambda, 599.8, 37.9, 31.0) + 0.362 * lobe(lambda, 442.0, 16.0, 26.7)
+            - 0.065 * lobe(lambda, 501.1, 20.4, 26.2),
+        0.821 * lobe(lambda, 568.8, 46.9, 40.5) + 0.286 * lobe(lambda, 530.9, 16.3, 31.1),
+        1.217 * lobe(lambda, 437.0, 11.8, 36.0) + 0.681 * lobe(lambda, 459.0, 26.0, 13.8),
+    )
+}
+
+pub fn xyz_to_rgb(xyz: &Vec3) -> Color {
+    Color::new(
+        3.2404542 * xyz.x - 1.5371385 * xyz.y - 0.4985314 * xyz.z,
+        -0.9692660 * xyz.x + 1.8760108 * xyz.y + 0.0415560 * xyz.z,
+        0.0556434 * xyz.x - 0.2040259 * xyz.y + 1.0572252 * xyz.z,
+    )
+}
+
+//per channel integral of the linear rgb response over the sampled range, so that a
+//constant spectrum maps to rgb (1, 1, 1)
+fn rgb_normalization() -> Color {
+    static NORM: OnceLock<Color> = OnceLock::new();
+    *NORM.get_or_init(|| {
+        let steps = 4700;
+        let dl = (LAMBDA_MAX - LAMBDA_MIN) / steps as f64;
+        let mut acc = Color::zero();
+        for i in 0..steps {
+            let lambda = LAMBDA_MIN + (i as f64 + 0.5) * dl;
+            acc += dl * xyz_to_rgb(&cie_xyz(lambda));
+        }
+        acc
+    })
+}
+
+//wavelengths distributed roughly like the eye's sensitivity (the visible wavelength pdf
+//from pbrt-v4), so that rgb estimates from few samples are far less noisy than uniform
+fn sample_visible_wavelength(u: f64) -> f64 {
+    538.0 - 138.888889 * (0.85691062 - 1.82750197 * u).atanh()
+}
+
+fn visible_wavelength_pdf(lambda: f64) -> f64 {
+    if !(LAMBDA_MIN..=LAMBDA_MAX).contains(&lambda) {
+        return 0.0;
+    }
+    0.0039398042 / (0.0072 * (lambda - 538.0)).cosh().powi(2)
+}
+
+//hero wavelength sampling (Wilkie et al. 2014): the hero wavelength is sampled from u and
+//its companions from u rotated evenly through [0, 1), all traced along the same path
+#[derive(Copy, Clone, Debug)]
+pub struct SampledWavelengths {
+    pub lambda: [f64; N_SAMPLES],
+    pub pdf: [f64; N_SAMPLES],
+}
+
+impl SampledWavelengths {
+    pub fn sample_visible(u: f64) -> Self {
+        let mut lambda = [0.0; N_SAMPLES];
+        let mut pdf = [0.0; N_SAMPLES];
+        for i in 0..N_SAMPLES {
+            let rotated = (u + i as f64 / N_SAMPLES as f64).fract();
+            lambda[i] = sample_visible_wavelength(rotated);
+            pdf[i] = visible_wavelength_pdf(lambda[i]);
+        }
+        Self { lambda, pdf }
+    }
+
+    pub fn hero(&self) -> f64 {
+        self.lambda[0]
+    }
+
+    //wavelength dependent scattering (e.g. dispersion) sends each wavelength its own way,
+    //so the path continues with the hero alone. the hero is then the only sample of the
+    //estimate, which its pdf accounts for
+    pub fn terminate_secondary(&mut self) {
+        if self.secondary_terminated() {
+            return;
+        }
+        for pdf in self.pdf.iter_mut().skip(1) {
+            *pdf = 0.0;
+        }
+        self.pdf[0] /= N_SAMPLES as f64;
+    }
+
+    pub fn secondary_terminated(&self) -> bool {
+        self.pdf[1..].iter().all(|&pdf| pdf == 0.0)
+    }
+
+    //rgb response to unit radiance at the sampled wavelengths through the cie matching
+    //functions. a monte carlo estimate that averages to white over many samples, so rgb
+    //radiance scaled by it stays unbiased while dispersed paths take on their hue
+    pub fn rgb_weight(&self) -> Color {
+        let mut acc = Color::zero();
+        for (lambda, pdf) in self.lambda.iter().zip(self.pdf.iter()) {
+            if *pdf > 0.0 {
+                acc += xyz_to_rgb(&cie_xyz(*lambda)) / *pdf;
+            }
+        }
+        acc / N_SAMPLES as f64 / rgb_normalization()
+    }
+}
+
+//wavelength dependent index of refraction, wavelengths given in nanometers
+#[derive(Copy, Clone, Debug)]
+pub enum Dispersion {
+    //n = a + b / lambda^2 with lambda in micrometers
+    Cauchy { a: f64, b: f64 },
+    //n^2 = 1 + sum b_i lambda^2 / (lambda^2 - c_i) with lambda in micrometers
+    Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+impl Dispersion {
+    pub fn ior(&self, lambda: f64) -> f64 {
+        let l2 = (lambda / 1000.0).powi(2);
+        match self {
+            Dispersion::Cauchy { a, b } => a + b / l2,
+            Dispersion::Sellmeier { b, c } => {
+                let sum: f64 = b.iter().zip(c.iter()).map(|(b, c)| b * l2 / (l2 - c)).sum();
+                (1.0 + sum).sqrt()
+            }
+        }
+    }
+
+    //ior at the sodium d line, the usual nominal value
+    pub fn nominal_ior(&self) -> f64 {
+        self.ior(589.3)
+    }
+
+    pub fn bk7() -> Self {
+        Dispersion::Sellmeier {
+            b: [1.03961212, 0.231792344, 1.01046945],
+            c: [0.00600069867, 0.0200179144, 103.560653],
+        }
+    }
+
+    pub fn fused_silica() -> Self {
+        Dispersion::Sellmeier {
+            b: [0.6961663, 0.4079426, 0.8974794],
+            c: [
+                0.0684043f64.powi(2),
+                0.1162414f64.powi(2),
+                9.896161f64.powi(2),
+            ],
+        }
+    }
+
+    pub fn dense_flint() -> Self {
+        Dispersion::Cauchy {
+            a: 1.7280,
+            b: 0.01342,
+        }
+    }
+
+    pub fn diamond() -> Self {
+        Dispersion::Sellmeier {
+            b: [0.3306, 4.3356, 0.0],
+            c: [0.1750f64.powi(2), 0.1060f64.powi(2), 0.0],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //averages rgb_weight over evenly spaced u, which should integrate to white
+    fn mean_weight(terminate: bool) -> Color {
+        let n = 4000;
+        let mut acc = Color::zero();
+        for i in 0..n {
+            let mut lambda = SampledWavelengths::sample_visible((i as f64 + 0.5) / n as f64);
+            if terminate {
+                lambda.terminate_secondary();
+            }
+            acc += lambda.rgb_weight();
+        }
+        acc / n as f64
+    }
+
+    #[test]
+    fn companions_rotate_through_the_range() {
+        let lambda = SampledWavelengths::sample_visible(0.3);
+        for (l, pdf) in lambda.lambda.iter().zip(lambda.pdf.iter()) {
+            assert!((LAMBDA_MIN..=LAMBDA_MAX).contains(l) && *pdf > 0.0);
+        }
+        assert!(!lambda.secondary_terminated());
+    }
+
+    #[test]
+    fn rgb_weight_averages_to_white() {
+        for terminate in [false, true] {
+            let w = mean_weight(terminate);
+            for c in [w.x, w.y, w.z] {
+                assert!((c - 1.0).abs() < 0.01, "terminate={terminate} {w:?}");
+            }
+        }
+    }
+}
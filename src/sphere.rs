@@ -1,6 +1,6 @@
-use crate::hittable::{HitRecord, Hittable};
+use crate::hittable::{HitRecord, Hittable, Periodic};
 use crate::materials::*;
-use crate::{Point, Ray, AABB};
+use crate::{Point, Ray, Vec3, AABB};
 
 pub struct Sphere<M: Mat> {
     pub center: Point,
@@ -23,11 +23,21 @@ impl<M: Mat> Sphere<M> {
 
         (phi / (2.0 * crate::PI), theta / crate::PI)
     }
+
+    //derivatives of the get_uv parameterization at outward unit normal n
+    pub fn get_tangents(n: &Vec3, radius: f64) -> (Vec3, Vec3) {
+        let sin_theta = f64::max((n.x * n.x + n.z * n.z).sqrt(), 1e-9);
+        let dpdu = 2.0 * crate::PI * radius * Vec3::new(n.z, 0.0, -n.x);
+        let dpdv = crate::PI
+            * radius
+            * Vec3::new(-n.x * n.y / sin_theta, sin_theta, -n.y * n.z / sin_theta);
+        (dpdu, dpdv)
+    }
 }
 
 impl<M: Mat> Hittable for Sphere<M> {
     //#[timed::timed(tracing(enabled = true), duration(disabled = true))]
-    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
         let oc = r.origin - self.center;
         let a = r.dir.l2();
         let half_b = r.dir.dot(&oc);
@@ -49,14 +59,16 @@ impl<M: Mat> Hittable for Sphere<M> {
         let t = root;
         let p = r.at(t);
         let normal = (p - self.center) / self.radius;
-        Some(HitRecord::new(
-            p,
-            t,
-            normal,
-            &r,
-            &self.material,
-            &Self::get_uv,
-        ))
+        let (dpdu, dpdv) = Self::get_tangents(&normal, self.radius);
+        Some(
+            HitRecord::new(p, t, normal, r, &self.material, Self::get_uv(&normal))
+                .with_tangents(dpdu, dpdv)
+                .with_differentials(
+                    r,
+                    &|q: &Point| Self::get_uv(&(*q - self.center).unit()),
+                    Periodic::U,
+                ),
+        )
     }
 
     fn bounding_box(&self) -> AABB {
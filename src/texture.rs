@@ -1,7 +1,15 @@
-use crate::{Color, Perlin, Point};
+use crate::image::invalid_data;
+use crate::{Color, Footprint, Image, Perlin, Point, Transform, Vec3};
+use std::fs;
+use std::io;
 
 pub trait Texture: Sync {
     fn value(&self, u: f64, v: f64, p: &Point) -> Color;
+
+    //lookup averaged over the footprint of a hit, point sampled unless overridden
+    fn value_filtered(&self, u: f64, v: f64, p: &Point, _footprint: &Footprint) -> Color {
+        self.value(u, v, p)
+    }
 }
 
 #[derive(Clone)]
@@ -26,27 +34,252 @@ impl Texture for Solid {
     }
 }
 
+//solid checker in world space
 #[derive(Clone)]
 pub struct Checkered<T: Texture, U: Texture> {
     odd: T,
     even: U,
+    pub scale: f64,
 }
 
 impl<T: Texture, U: Texture> Checkered<T, U> {
     pub fn new(odd: T, even: U) -> Self {
-        Checkered { odd, even }
+        Self::with_scale(odd, even, 10.0)
+    }
+
+    pub fn with_scale(odd: T, even: U, scale: f64) -> Self {
+        Checkered { odd, even, scale }
+    }
+
+    fn is_odd(&self, p: &Point) -> bool {
+        let s = self.scale;
+        (s * p.x).sin() * (s * p.y).sin() * (s * p.z).sin() < 0.0
     }
 }
 
 impl<T: Texture, U: Texture> Texture for Checkered<T, U> {
     fn value(&self, u: f64, v: f64, p: &Point) -> Color {
-        let sines = (10.0 * p.x).sin() * (10.0 * p.y).sin() * (10.0 * p.z).sin();
-        if sines < 0.0 {
+        if self.is_odd(p) {
             self.odd.value(u, v, p)
         } else {
             self.even.value(u, v, p)
         }
     }
+
+    fn value_filtered(&self, u: f64, v: f64, p: &Point, footprint: &Footprint) -> Color {
+        if self.is_odd(p) {
+            self.odd.value_filtered(u, v, p, footprint)
+        } else {
+            self.even.value_filtered(u, v, p, footprint)
+        }
+    }
+}
+
+//checker in uv space with scale_u by scale_v squares over the unit square
+#[derive(Clone)]
+pub struct UvChecker<T: Texture, U: Texture> {
+    odd: T,
+    even: U,
+    pub scale_u: f64,
+    pub scale_v: f64,
+}
+
+impl<T: Texture, U: Texture> UvChecker<T, U> {
+    pub fn new(odd: T, even: U, scale_u: f64, scale_v: f64) -> Self {
+        Self {
+            odd,
+            even,
+            scale_u,
+            scale_v,
+        }
+    }
+
+    fn is_odd(&self, u: f64, v: f64) -> bool {
+        let cell = (self.scale_u * u).floor() as i64 + (self.scale_v * v).floor() as i64;
+        cell.rem_euclid(2) == 1
+    }
+}
+
+impl<T: Texture, U: Texture> Texture for UvChecker<T, U> {
+    fn value(&self, u: f64, v: f64, p: &Point) -> Color {
+        if self.is_odd(u, v) {
+            self.odd.value(u, v, p)
+        } else {
+            self.even.value(u, v, p)
+        }
+    }
+
+    fn value_filtered(&self, u: f64, v: f64, p: &Point, footprint: &Footprint) -> Color {
+        if self.is_odd(u, v) {
+            self.odd.value_filtered(u, v, p, footprint)
+        } else {
+            self.even.value_filtered(u, v, p, footprint)
+        }
+    }
+}
+
+//scales, then rotates (degrees, counterclockwise), then offsets the uvs seen by a texture
+#[derive(Clone)]
+pub struct UvTransform<T: Texture> {
+    inner: T,
+    pub scale: (f64, f64),
+    pub rotation: f64,
+    pub offset: (f64, f64),
+}
+
+impl<T: Texture> UvTransform<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            scale: (1.0, 1.0),
+            rotation: 0.0,
+            offset: (0.0, 0.0),
+        }
+    }
+
+    pub fn scale(mut self, su: f64, sv: f64) -> Self {
+        self.scale = (su, sv);
+        self
+    }
+
+    pub fn rotate(mut self, degrees: f64) -> Self {
+        self.rotation = degrees;
+        self
+    }
+
+    pub fn translate(mut self, du: f64, dv: f64) -> Self {
+        self.offset = (du, dv);
+        self
+    }
+
+    //linear part only, used for both uvs and their derivatives
+    fn linear(&self, u: f64, v: f64) -> (f64, f64) {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        let (u, v) = (u * self.scale.0, v * self.scale.1);
+        (cos * u - sin * v, sin * u + cos * v)
+    }
+
+    fn apply(&self, u: f64, v: f64) -> (f64, f64) {
+        let (u, v) = self.linear(u, v);
+        (u + self.offset.0, v + self.offset.1)
+    }
+}
+
+impl<T: Texture> Texture for UvTransform<T> {
+    fn value(&self, u: f64, v: f64, p: &Point) -> Color {
+        let (u, v) = self.apply(u, v);
+        self.inner.value(u, v, p)
+    }
+
+    fn value_filtered(&self, u: f64, v: f64, p: &Point, footprint: &Footprint) -> Color {
+        let (u, v) = self.apply(u, v);
+        let (dudx, dvdx) = self.linear(footprint.dudx, footprint.dvdx);
+        let (dudy, dvdy) = self.linear(footprint.dudy, footprint.dvdy);
+        let footprint = Footprint {
+            dudx,
+            dvdx,
+            dudy,
+            dvdy,
+            ..*footprint
+        };
+        self.inner.value_filtered(u, v, p, &footprint)
+    }
+}
+
+//evaluates a 3d texture in its own object space, given the transform from that space
+//to world space
+#[derive(Clone)]
+pub struct SolidTransform<T: Texture> {
+    inner: T,
+    pub transform: Transform,
+}
+
+impl<T: Texture> SolidTransform<T> {
+    pub fn new(inner: T, transform: Transform) -> Self {
+        Self { inner, transform }
+    }
+}
+
+impl<T: Texture> Texture for SolidTransform<T> {
+    fn value(&self, u: f64, v: f64, p: &Point) -> Color {
+        self.inner.value(u, v, &self.transform.inverse_point(p))
+    }
+
+    fn value_filtered(&self, u: f64, v: f64, p: &Point, footprint: &Footprint) -> Color {
+        self.inner
+            .value_filtered(u, v, &self.transform.inverse_point(p), footprint)
+    }
+}
+
+//per channel linear blend, a where the mask is 0 and b where it is 1
+#[derive(Clone)]
+pub struct Mix<A: Texture, B: Texture, M: Texture> {
+    a: A,
+    b: B,
+    mask: M,
+}
+
+impl<A: Texture, B: Texture, M: Texture> Mix<A, B, M> {
+    pub fn new(a: A, b: B, mask: M) -> Self {
+        Self { a, b, mask }
+    }
+}
+
+impl<A: Texture, B: Texture, M: Texture> Texture for Mix<A, B, M> {
+    fn value(&self, u: f64, v: f64, p: &Point) -> Color {
+        let m = self.mask.value(u, v, p);
+        (Color::new(1.0, 1.0, 1.0) - m) * self.a.value(u, v, p) + m * self.b.value(u, v, p)
+    }
+
+    fn value_filtered(&self, u: f64, v: f64, p: &Point, footprint: &Footprint) -> Color {
+        let m = self.mask.value_filtered(u, v, p, footprint);
+        (Color::new(1.0, 1.0, 1.0) - m) * self.a.value_filtered(u, v, p, footprint)
+            + m * self.b.value_filtered(u, v, p, footprint)
+    }
+}
+
+#[derive(Clone)]
+pub struct Product<A: Texture, B: Texture> {
+    a: A,
+    b: B,
+}
+
+impl<A: Texture, B: Texture> Product<A, B> {
+    pub fn new(a: A, b: B) -> Self {
+        Self { a, b }
+    }
+}
+
+impl<A: Texture, B: Texture> Texture for Product<A, B> {
+    fn value(&self, u: f64, v: f64, p: &Point) -> Color {
+        self.a.value(u, v, p) * self.b.value(u, v, p)
+    }
+
+    fn value_filtered(&self, u: f64, v: f64, p: &Point, footprint: &Footprint) -> Color {
+        self.a.value_filtered(u, v, p, footprint) * self.b.value_filtered(u, v, p, footprint)
+    }
+}
+
+#[derive(Clone)]
+pub struct Sum<A: Texture, B: Texture> {
+    a: A,
+    b: B,
+}
+
+impl<A: Texture, B: Texture> Sum<A, B> {
+    pub fn new(a: A, b: B) -> Self {
+        Self { a, b }
+    }
+}
+
+impl<A: Texture, B: Texture> Texture for Sum<A, B> {
+    fn value(&self, u: f64, v: f64, p: &Point) -> Color {
+        self.a.value(u, v, p) + self.b.value(u, v, p)
+    }
+
+    fn value_filtered(&self, u: f64, v: f64, p: &Point, footprint: &Footprint) -> Color {
+        self.a.value_filtered(u, v, p, footprint) + self.b.value_filtered(u, v, p, footprint)
+    }
 }
 
 #[derive(Clone)]
@@ -54,16 +287,536 @@ pub struct Noisy {
     noise: Perlin,
 }
 
+impl Default for Noisy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Noisy {
     pub fn new() -> Self {
         Self {
             noise: Perlin::new(),
         }
     }
+
+    pub fn with_frequency(frequency: f64) -> Self {
+        Self {
+            noise: Perlin::with_frequency(frequency),
+        }
+    }
 }
 
 impl Texture for Noisy {
     fn value(&self, _u: f64, _v: f64, p: &Point) -> Color {
-        Color::new(1.0, 1.0, 1.0) * self.noise.noise(p)
+        //remap gradient noise from [-1, 1] to [0, 1]
+        Color::new(1.0, 1.0, 1.0) * 0.5 * (1.0 + self.noise.noise(p))
+    }
+}
+
+//gray perlin turbulence, useful as a density field for smoke and clouds
+#[derive(Clone)]
+pub struct Turbulence {
+    noise: Perlin,
+    pub scale: f64,
+    pub octaves: u32,
+}
+
+impl Turbulence {
+    pub fn new(scale: f64) -> Self {
+        Self {
+            noise: Perlin::new(),
+            scale,
+            octaves: 7,
+        }
+    }
+
+    pub fn with_octaves(mut self, octaves: u32) -> Self {
+        self.octaves = octaves;
+        self
+    }
+}
+
+impl Texture for Turbulence {
+    fn value(&self, _u: f64, _v: f64, p: &Point) -> Color {
+        let t = self.noise.turb(&(self.scale * *p), self.octaves);
+        Color::new(t, t, t)
+    }
+}
+
+//piecewise linear gradient between colors, indexed by t in [0, 1]
+#[derive(Clone)]
+pub struct ColorRamp {
+    stops: Vec<(f64, Color)>,
+}
+
+impl ColorRamp {
+    pub fn new(mut stops: Vec<(f64, Color)>) -> Self {
+        if stops.is_empty() {
+            panic!("cannot create color ramp without stops");
+        }
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { stops }
+    }
+
+    pub fn two_tone(low: Color, high: Color) -> Self {
+        Self::new(vec![(0.0, low), (1.0, high)])
+    }
+
+    pub fn sample(&self, t: f64) -> Color {
+        let first = self.stops[0];
+        let last = self.stops[self.stops.len() - 1];
+        if t <= first.0 {
+            return first.1;
+        }
+        if t >= last.0 {
+            return last.1;
+        }
+        let i = self.stops.partition_point(|(pos, _)| *pos <= t);
+        let (t0, c0) = self.stops[i - 1];
+        let (t1, c1) = self.stops[i];
+        let s = (t - t0) / (t1 - t0);
+        (1.0 - s) * c0 + s * c1
+    }
+}
+
+//maps fbm noise through a color ramp
+#[derive(Clone)]
+pub struct NoiseTexture {
+    noise: Perlin,
+    pub ramp: ColorRamp,
+    pub scale: f64,
+    pub octaves: u32,
+    pub lacunarity: f64,
+    pub gain: f64,
+}
+
+impl NoiseTexture {
+    pub fn new(ramp: ColorRamp, scale: f64) -> Self {
+        Self {
+            noise: Perlin::new(),
+            ramp,
+            scale,
+            octaves: 7,
+            lacunarity: 2.0,
+            gain: 0.5,
+        }
+    }
+
+    pub fn with_octaves(mut self, octaves: u32, lacunarity: f64, gain: f64) -> Self {
+        self.octaves = octaves;
+        self.lacunarity = lacunarity;
+        self.gain = gain;
+        self
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f64, _v: f64, p: &Point) -> Color {
+        let n = self
+            .noise
+            .fbm(&(self.scale * *p), self.octaves, self.lacunarity, self.gain);
+        self.ramp.sample(0.5 * (1.0 + n))
+    }
+}
+
+//veins from a sine wave along z whose phase is perturbed by turbulence
+#[derive(Clone)]
+pub struct Marble {
+    noise: Perlin,
+    pub ramp: ColorRamp,
+    pub scale: f64,
+    pub turbulence: f64,
+    pub octaves: u32,
+    pub lacunarity: f64,
+    pub gain: f64,
+}
+
+impl Marble {
+    pub fn new(scale: f64) -> Self {
+        Self {
+            noise: Perlin::new(),
+            ramp: ColorRamp::two_tone(Color::zero(), Color::new(1.0, 1.0, 1.0)),
+            scale,
+            turbulence: 10.0,
+            octaves: 7,
+            lacunarity: 2.0,
+            gain: 0.5,
+        }
+    }
+
+    pub fn with_colors(mut self, ramp: ColorRamp) -> Self {
+        self.ramp = ramp;
+        self
+    }
+
+    pub fn with_octaves(mut self, octaves: u32, lacunarity: f64, gain: f64) -> Self {
+        self.octaves = octaves;
+        self.lacunarity = lacunarity;
+        self.gain = gain;
+        self
+    }
+}
+
+impl Texture for Marble {
+    fn value(&self, _u: f64, _v: f64, p: &Point) -> Color {
+        let turb = self
+            .noise
+            .turbulence(p, self.octaves, self.lacunarity, self.gain);
+        let phase = self.scale * p.z + self.turbulence * turb;
+        self.ramp.sample(0.5 * (1.0 + phase.sin()))
+    }
+}
+
+//concentric rings around the y axis, warped by turbulence
+#[derive(Clone)]
+pub struct Wood {
+    noise: Perlin,
+    pub ramp: ColorRamp,
+    pub scale: f64,
+    pub turbulence: f64,
+    pub octaves: u32,
+    pub lacunarity: f64,
+    pub gain: f64,
+}
+
+impl Wood {
+    pub fn new(scale: f64) -> Self {
+        Self {
+            noise: Perlin::new(),
+            ramp: ColorRamp::two_tone(Color::new(0.35, 0.18, 0.07), Color::new(0.75, 0.5, 0.25)),
+            scale,
+            turbulence: 0.5,
+            octaves: 4,
+            lacunarity: 2.0,
+            gain: 0.5,
+        }
+    }
+
+    pub fn with_colors(mut self, ramp: ColorRamp) -> Self {
+        self.ramp = ramp;
+        self
+    }
+
+    pub fn with_octaves(mut self, octaves: u32, lacunarity: f64, gain: f64) -> Self {
+        self.octaves = octaves;
+        self.lacunarity = lacunarity;
+        self.gain = gain;
+        self
+    }
+}
+
+impl Texture for Wood {
+    fn value(&self, _u: f64, _v: f64, p: &Point) -> Color {
+        let turb = self
+            .noise
+            .turbulence(p, self.octaves, self.lacunarity, self.gain);
+        let rings = self.scale * (p.x * p.x + p.z * p.z).sqrt() + self.turbulence * turb;
+        let t = rings - rings.floor();
+        //sharpen the transition so rings read as late wood against early wood
+        self.ramp.sample(t * t)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Filter {
+    Nearest,
+    Bilinear,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WrapMode {
+    Repeat,
+    Clamp,
+    Mirror,
+}
+
+impl WrapMode {
+    fn apply(&self, i: i32, n: i32) -> i32 {
+        match self {
+            WrapMode::Repeat => i.rem_euclid(n),
+            WrapMode::Clamp => i.clamp(0, n - 1),
+            WrapMode::Mirror => {
+                let m = i.rem_euclid(2 * n);
+                if m >= n {
+                    2 * n - 1 - m
+                } else {
+                    m
+                }
+            }
+        }
+    }
+}
+
+//how stored 8-bit texel values are encoded. color maps are usually srgb,
+//data maps (normals, roughness, masks) are linear. hdr files are always linear
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ColorSpace {
+    Srgb,
+    Linear,
+}
+
+fn srgb_to_linear(c: f64) -> f64 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+//halves an image in each dimension with a box filter, clamping at odd edges
+fn downsample(img: &Image) -> Image {
+    let width = (img.width() / 2).max(1);
+    let height = (img.height() / 2).max(1);
+    let mut res = Image::new(height, width);
+    for y in 0..height {
+        for x in 0..width {
+            let x0 = (2 * x).min(img.width() - 1);
+            let x1 = (2 * x + 1).min(img.width() - 1);
+            let y0 = (2 * y).min(img.height() - 1);
+            let y1 = (2 * y + 1).min(img.height() - 1);
+            res.push(
+                0.25 * (img.get(x0, y0) + img.get(x1, y0) + img.get(x0, y1) + img.get(x1, y1)),
+            );
+        }
+    }
+    res
+}
+
+pub struct ImageTexture {
+    //mip pyramid, level 0 is the full resolution image
+    levels: Vec<Image>,
+    pub filter: Filter,
+    pub wrap: WrapMode,
+    pub uv_scale: (f64, f64),
+    pub uv_offset: (f64, f64),
+}
+
+impl ImageTexture {
+    pub fn new(texels: Image) -> Self {
+        if texels.width() == 0 || texels.height() == 0 {
+            panic!("cannot create texture from empty image");
+        }
+        let mut levels = vec![texels];
+        loop {
+            let last = &levels[levels.len() - 1];
+            if last.width() == 1 && last.height() == 1 {
+                break;
+            }
+            let next = downsample(last);
+            levels.push(next);
+        }
+        Self {
+            levels,
+            filter: Filter::Bilinear,
+            wrap: WrapMode::Repeat,
+            uv_scale: (1.0, 1.0),
+            uv_offset: (0.0, 0.0),
+        }
+    }
+
+    pub fn load(path: &str, color_space: ColorSpace) -> io::Result<Self> {
+        let mut texels = Image::load(path)?;
+        let is_hdr = path.to_ascii_lowercase().ends_with(".hdr");
+        if color_space == ColorSpace::Srgb && !is_hdr {
+            for c in texels.image.iter_mut() {
+                *c = Color::new(
+                    srgb_to_linear(c.x),
+                    srgb_to_linear(c.y),
+                    srgb_to_linear(c.z),
+                );
+            }
+        }
+        Ok(Self::new(texels))
+    }
+
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn with_wrap(mut self, wrap: WrapMode) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn with_uv_transform(mut self, scale: (f64, f64), offset: (f64, f64)) -> Self {
+        self.uv_scale = scale;
+        self.uv_offset = offset;
+        self
+    }
+
+    fn texel(&self, level: &Image, x: i32, y: i32) -> Color {
+        let x = self.wrap.apply(x, level.width());
+        let y = self.wrap.apply(y, level.height());
+        level.get(x, y)
+    }
+
+    //samples one mip level at already transformed uvs
+    fn lookup(&self, level: usize, u: f64, v: f64) -> Color {
+        let level = &self.levels[level];
+
+        //v runs bottom to top, image rows are stored top to bottom
+        let x = u * level.width() as f64;
+        let y = (1.0 - v) * level.height() as f64;
+
+        match self.filter {
+            Filter::Nearest => self.texel(level, x.floor() as i32, y.floor() as i32),
+            Filter::Bilinear => {
+                //texel centers sit at half integer coordinates
+                let x = x - 0.5;
+                let y = y - 0.5;
+                let x0 = x.floor();
+                let y0 = y.floor();
+                let dx = x - x0;
+                let dy = y - y0;
+                let (x0, y0) = (x0 as i32, y0 as i32);
+
+                (1.0 - dx) * (1.0 - dy) * self.texel(level, x0, y0)
+                    + dx * (1.0 - dy) * self.texel(level, x0 + 1, y0)
+                    + (1.0 - dx) * dy * self.texel(level, x0, y0 + 1)
+                    + dx * dy * self.texel(level, x0 + 1, y0 + 1)
+            }
+        }
+    }
+
+    fn transform_uv(&self, u: f64, v: f64) -> (f64, f64) {
+        (
+            u * self.uv_scale.0 + self.uv_offset.0,
+            v * self.uv_scale.1 + self.uv_offset.1,
+        )
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: &Point) -> Color {
+        let (u, v) = self.transform_uv(u, v);
+        self.lookup(0, u, v)
+    }
+
+    //trilinear filtering: picks the two mip levels whose texel size brackets the
+    //footprint width and blends between them
+    fn value_filtered(&self, u: f64, v: f64, _p: &Point, footprint: &Footprint) -> Color {
+        let (u, v) = self.transform_uv(u, v);
+        let width = self.levels[0].width() as f64 * self.uv_scale.0.abs();
+        let height = self.levels[0].height() as f64 * self.uv_scale.1.abs();
+        let texels = f64::max(
+            f64::max(footprint.dudx.abs(), footprint.dudy.abs()) * width,
+            f64::max(footprint.dvdx.abs(), footprint.dvdy.abs()) * height,
+        );
+
+        let max_level = (self.levels.len() - 1) as f64;
+        let lod = if texels > 1.0 {
+            texels.log2().min(max_level)
+        } else {
+            0.0
+        };
+        let lower = lod.floor();
+        let t = lod - lower;
+        if t == 0.0 {
+            return self.lookup(lower as usize, u, v);
+        }
+        (1.0 - t) * self.lookup(lower as usize, u, v) + t * self.lookup(lower as usize + 1, u, v)
+    }
+}
+
+//dense grid of scalar samples stretched over the box from min to max, looked up with
+//trilinear interpolation between cell centers and zero outside the box
+#[derive(Clone)]
+pub struct VoxelGrid {
+    dims: (usize, usize, usize),
+    data: Vec<f64>,
+    pub min: Point,
+    pub max: Point,
+}
+
+impl VoxelGrid {
+    //data is ordered with x varying fastest, then y, then z
+    pub fn new(dims: (usize, usize, usize), data: Vec<f64>, min: Point, max: Point) -> Self {
+        if dims.0 * dims.1 * dims.2 == 0 || data.len() != dims.0 * dims.1 * dims.2 {
+            panic!("voxel data does not match grid dimensions");
+        }
+        Self {
+            dims,
+            data,
+            min,
+            max,
+        }
+    }
+
+    //reads a whitespace separated text file: the dimensions nx ny nz followed by
+    //nx * ny * nz values in the order expected by new
+    pub fn load(path: &str, min: Point, max: Point) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        let mut tokens = contents.split_whitespace();
+        let mut dims = [0usize; 3];
+        for d in dims.iter_mut() {
+            let token = tokens
+                .next()
+                .ok_or_else(|| invalid_data("missing voxel grid dimensions".to_string()))?;
+            *d = token
+                .parse()
+                .map_err(|_| invalid_data(format!("expected dimension, found {token}")))?;
+        }
+        let data = tokens
+            .map(|token| {
+                token
+                    .parse()
+                    .map_err(|_| invalid_data(format!("expected density, found {token}")))
+            })
+            .collect::<io::Result<Vec<f64>>>()?;
+        if dims.iter().product::<usize>() == 0 || data.len() != dims.iter().product::<usize>() {
+            return Err(invalid_data(format!(
+                "expected {} voxels, found {}",
+                dims.iter().product::<usize>(),
+                data.len()
+            )));
+        }
+        Ok(Self::new((dims[0], dims[1], dims[2]), data, min, max))
+    }
+
+    //largest sample, a tight majorant for tracking through the grid
+    pub fn max_value(&self) -> f64 {
+        self.data.iter().cloned().fold(0.0, f64::max)
+    }
+
+    fn voxel(&self, x: usize, y: usize, z: usize) -> f64 {
+        self.data[(z * self.dims.1 + y) * self.dims.0 + x]
+    }
+
+    pub fn sample(&self, p: &Point) -> f64 {
+        let extent = self.max - self.min;
+        let rel = *p - self.min;
+        let local = Vec3::new(rel.x / extent.x, rel.y / extent.y, rel.z / extent.z);
+        if [local.x, local.y, local.z]
+            .iter()
+            .any(|c| !(0.0..=1.0).contains(c))
+        {
+            return 0.0;
+        }
+
+        //continuous grid coordinates with samples at cell centers, clamped at the edges
+        let coord = |c: f64, n: usize| {
+            let g = (c * n as f64 - 0.5).clamp(0.0, (n - 1) as f64);
+            let i = (g.floor() as usize).min(n.saturating_sub(2));
+            (i, (i + 1).min(n - 1), g - i as f64)
+        };
+        let (x0, x1, tx) = coord(local.x, self.dims.0);
+        let (y0, y1, ty) = coord(local.y, self.dims.1);
+        let (z0, z1, tz) = coord(local.z, self.dims.2);
+
+        let lerp = |a: f64, b: f64, t: f64| (1.0 - t) * a + t * b;
+        let c00 = lerp(self.voxel(x0, y0, z0), self.voxel(x1, y0, z0), tx);
+        let c10 = lerp(self.voxel(x0, y1, z0), self.voxel(x1, y1, z0), tx);
+        let c01 = lerp(self.voxel(x0, y0, z1), self.voxel(x1, y0, z1), tx);
+        let c11 = lerp(self.voxel(x0, y1, z1), self.voxel(x1, y1, z1), tx);
+        lerp(lerp(c00, c10, ty), lerp(c01, c11, ty), tz)
+    }
+}
+
+impl Texture for VoxelGrid {
+    fn value(&self, _u: f64, _v: f64, p: &Point) -> Color {
+        let d = self.sample(p);
+        Color::new(d, d, d)
     }
 }
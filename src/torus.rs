@@ -0,0 +1,110 @@
+//module for tori, intersected by solving a quartic
+use crate::frame::{azimuth, disk_extent};
+use crate::hittable::{HitRecord, Hittable, Periodic};
+use crate::materials::*;
+use crate::polynomial::solve_quartic;
+use crate::{Frame, Point, Ray, Vec3, AABB, PI};
+
+//ring of tube radius minor_radius swept around a circle of major_radius about axis.
+//u runs around the axis and v around the tube
+pub struct Torus<M: Mat> {
+    pub center: Point,
+    pub major_radius: f64,
+    pub minor_radius: f64,
+    pub material: M,
+    //local space with the axis along z
+    frame: Frame,
+}
+
+impl<M: Mat> Torus<M> {
+    pub fn new(
+        center: Point,
+        axis: Vec3,
+        major_radius: f64,
+        minor_radius: f64,
+        material: M,
+    ) -> Self {
+        Self {
+            center,
+            major_radius,
+            minor_radius,
+            material,
+            frame: Frame::from_normal(axis.unit()),
+        }
+    }
+
+    fn get_uv(&self, p: &Point) -> (f64, f64) {
+        let local = self.frame.to_local(&(*p - self.center));
+        let rho = (local.x * local.x + local.y * local.y).sqrt();
+        let theta = local.z.atan2(rho - self.major_radius);
+        let theta = if theta < 0.0 { theta + 2.0 * PI } else { theta };
+        (azimuth(&local) / (2.0 * PI), theta / (2.0 * PI))
+    }
+}
+
+impl<M: Mat> Hittable for Torus<M> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        //solve with a unit direction from the point nearest the center, which keeps the
+        //quartic's coefficients well conditioned for distant rays
+        let length = r.dir.length();
+        let d = self.frame.to_local(&r.dir) / length;
+        let o_far = self.frame.to_local(&(r.origin - self.center));
+        let shift = -o_far.dot(&d);
+        let o = o_far + shift * d;
+
+        let (big_r2, small_r2) = (
+            self.major_radius * self.major_radius,
+            self.minor_radius * self.minor_radius,
+        );
+        let e = o.dot(&d);
+        let f = o.l2() + big_r2 - small_r2;
+        let coeffs = [
+            1.0,
+            4.0 * e,
+            2.0 * f + 4.0 * e * e - 4.0 * big_r2 * (d.x * d.x + d.y * d.y),
+            4.0 * e * f - 8.0 * big_r2 * (o.x * d.x + o.y * d.y),
+            f * f - 4.0 * big_r2 * (o.x * o.x + o.y * o.y),
+        ];
+        let t = solve_quartic(coeffs)
+            .into_iter()
+            .map(|s| (s + shift) / length)
+            .find(|t| *t >= t_min && *t <= t_max)?;
+
+        let p = r.at(t);
+        let local = self.frame.to_local(&(p - self.center));
+        let (u, v) = self.get_uv(&p);
+        let (sin_phi, cos_phi) = (2.0 * PI * u).sin_cos();
+        let (sin_theta, cos_theta) = (2.0 * PI * v).sin_cos();
+        //the normal points from the nearest point on the core circle
+        let core = self.major_radius * Vec3::new(cos_phi, sin_phi, 0.0);
+        let normal = (local - core).unit();
+        let ring = self.major_radius + self.minor_radius * cos_theta;
+        let dpdu = 2.0 * PI * ring * Vec3::new(-sin_phi, cos_phi, 0.0);
+        let dpdv = 2.0
+            * PI
+            * self.minor_radius
+            * Vec3::new(-sin_theta * cos_phi, -sin_theta * sin_phi, cos_theta);
+
+        Some(
+            HitRecord::new(
+                p,
+                t,
+                self.frame.to_world(&normal),
+                r,
+                &self.material,
+                (u, v),
+            )
+            .with_tangents(self.frame.to_world(&dpdu), self.frame.to_world(&dpdv))
+            .with_differentials(r, &|q: &Point| self.get_uv(q), Periodic::UV),
+        )
+    }
+
+    fn bounding_box(&self) -> AABB {
+        let extent = disk_extent(&self.frame.n, self.major_radius)
+            + Vec3::new(self.minor_radius, self.minor_radius, self.minor_radius);
+        AABB {
+            min: self.center - extent,
+            max: self.center + extent,
+        }
+    }
+}
@@ -0,0 +1,270 @@
+//module for affine transforms stored as 4x4 matrices alongside their inverse
+use crate::{Point, Vec3};
+
+type Mat4 = [[f64; 4]; 4];
+
+const IDENTITY: Mat4 = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+fn mul(a: &Mat4, b: &Mat4) -> Mat4 {
+    let mut res = [[0.0; 4]; 4];
+    for (i, row) in res.iter_mut().enumerate() {
+        for (j, entry) in row.iter_mut().enumerate() {
+            *entry = (0..4).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    res
+}
+
+fn transpose(m: &Mat4) -> Mat4 {
+    let mut res = [[0.0; 4]; 4];
+    for (i, row) in res.iter_mut().enumerate() {
+        for (j, entry) in row.iter_mut().enumerate() {
+            *entry = m[j][i];
+        }
+    }
+    res
+}
+
+//gauss-jordan elimination with partial pivoting
+fn invert(m: &Mat4) -> Option<Mat4> {
+    let mut a = *m;
+    let mut inv = IDENTITY;
+    for col in 0..4 {
+        let pivot = (col..4).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        //also rejects nan, which total_cmp ranks above every number
+        if a[pivot][col].is_nan() || a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        inv.swap(col, pivot);
+
+        let p = a[col][col];
+        for j in 0..4 {
+            a[col][j] /= p;
+            inv[col][j] /= p;
+        }
+        for i in 0..4 {
+            if i != col {
+                let f = a[i][col];
+                for j in 0..4 {
+                    a[i][j] -= f * a[col][j];
+                    inv[i][j] -= f * inv[col][j];
+                }
+            }
+        }
+    }
+    Some(inv)
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Transform {
+    m: Mat4,
+    inv: Mat4,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        Self {
+            m: IDENTITY,
+            inv: IDENTITY,
+        }
+    }
+
+    //panics if the matrix is singular
+    pub fn from_matrix(m: [[f64; 4]; 4]) -> Self {
+        let inv = invert(&m).expect("cannot create transform from singular matrix");
+        Self { m, inv }
+    }
+
+    pub fn translate(offset: Vec3) -> Self {
+        let mut m = IDENTITY;
+        let mut inv = IDENTITY;
+        for i in 0..3 {
+            m[i][3] = offset.get(i as i32);
+            inv[i][3] = -offset.get(i as i32);
+        }
+        Self { m, inv }
+    }
+
+    pub fn scale(factors: Vec3) -> Self {
+        let mut m = IDENTITY;
+        let mut inv = IDENTITY;
+        for i in 0..3 {
+            m[i][i] = factors.get(i as i32);
+            inv[i][i] = 1.0 / factors.get(i as i32);
+        }
+        Self { m, inv }
+    }
+
+    pub fn uniform_scale(factor: f64) -> Self {
+        Self::scale(Vec3::new(factor, factor, factor))
+    }
+
+    //rotation by angle degrees counterclockwise about axis
+    pub fn rotate(axis: Vec3, degrees: f64) -> Self {
+        let a = axis.unit();
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let m = [
+            [
+                a.x * a.x + (1.0 - a.x * a.x) * cos,
+                a.x * a.y * (1.0 - cos) - a.z * sin,
+                a.x * a.z * (1.0 - cos) + a.y * sin,
+                0.0,
+            ],
+            [
+                a.x * a.y * (1.0 - cos) + a.z * sin,
+                a.y * a.y + (1.0 - a.y * a.y) * cos,
+                a.y * a.z * (1.0 - cos) - a.x * sin,
+                0.0,
+            ],
+            [
+                a.x * a.z * (1.0 - cos) - a.y * sin,
+                a.y * a.z * (1.0 - cos) + a.x * sin,
+                a.z * a.z + (1.0 - a.z * a.z) * cos,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+        //rotations are orthogonal so the inverse is the transpose
+        Self {
+            m,
+            inv: transpose(&m),
+        }
+    }
+
+    pub fn rotate_x(degrees: f64) -> Self {
+        Self::rotate(Vec3::new(1.0, 0.0, 0.0), degrees)
+    }
+
+    pub fn rotate_y(degrees: f64) -> Self {
+        Self::rotate(Vec3::new(0.0, 1.0, 0.0), degrees)
+    }
+
+    pub fn rotate_z(degrees: f64) -> Self {
+        Self::rotate(Vec3::new(0.0, 0.0, 1.0), degrees)
+    }
+
+    //composition that applies self first, then other
+    pub fn then(&self, other: &Transform) -> Transform {
+        Transform {
+            m: mul(&other.m, &self.m),
+            inv: mul(&self.inv, &other.inv),
+        }
+    }
+
+    pub fn inverse(&self) -> Transform {
+        Transform {
+            m: self.inv,
+            inv: self.m,
+        }
+    }
+
+    pub fn point(&self, p: &Point) -> Point {
+        apply_point(&self.m, p)
+    }
+
+    pub fn vector(&self, v: &Vec3) -> Vec3 {
+        apply_vector(&self.m, v)
+    }
+
+    //normals transform by the inverse transpose to stay perpendicular to surfaces
+    pub fn normal(&self, n: &Vec3) -> Vec3 {
+        apply_vector(&transpose(&self.inv), n)
+    }
+
+    pub fn inverse_point(&self, p: &Point) -> Point {
+        apply_point(&self.inv, p)
+    }
+
+    pub fn inverse_vector(&self, v: &Vec3) -> Vec3 {
+        apply_vector(&self.inv, v)
+    }
+}
+
+fn apply_point(m: &Mat4, p: &Point) -> Point {
+    let v = apply_vector(m, p) + Vec3::new(m[0][3], m[1][3], m[2][3]);
+    let w = m[3][0] * p.x + m[3][1] * p.y + m[3][2] * p.z + m[3][3];
+    if w == 1.0 {
+        v
+    } else {
+        v / w
+    }
+}
+
+fn apply_vector(m: &Mat4, v: &Vec3) -> Vec3 {
+    Vec3::new(
+        m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+        m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+        m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_identity(m: &Mat4) {
+        for (i, row) in m.iter().enumerate() {
+            for (j, entry) in row.iter().enumerate() {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((entry - expected).abs() < 1e-12, "{m:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn invert_round_trips() {
+        let m = [
+            [2.0, 0.0, 1.0, 3.0],
+            [1.0, 3.0, 0.0, -1.0],
+            [0.0, 1.0, 4.0, 2.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+        let inv = invert(&m).unwrap();
+        assert_identity(&mul(&inv, &m));
+        assert_identity(&mul(&m, &inv));
+    }
+
+    #[test]
+    fn invert_needs_pivoting() {
+        //zero on the diagonal forces row swaps
+        let m = [
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 2.0, 0.0],
+            [3.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+        let inv = invert(&m).unwrap();
+        assert_identity(&mul(&inv, &m));
+    }
+
+    #[test]
+    fn invert_singular() {
+        //third row is the sum of the first two
+        let m = [
+            [1.0, 2.0, 3.0, 0.0],
+            [0.0, 1.0, 4.0, 0.0],
+            [1.0, 3.0, 7.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+        assert!(invert(&m).is_none());
+    }
+
+    #[test]
+    fn invert_nan() {
+        let mut m = IDENTITY;
+        m[2][2] = f64::NAN;
+        assert!(invert(&m).is_none());
+    }
+}
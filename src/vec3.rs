@@ -20,10 +20,6 @@ impl Vec3 {
         }
     }
 
-    pub fn default() -> Self {
-        Self::zero()
-    }
-
     pub fn rand(min: f64, max: f64) -> Self {
         Vec3 {
             x: rand_range(min, max),
@@ -113,6 +109,10 @@ impl Vec3 {
         }
     }
 
+    pub fn mean(&self) -> f64 {
+        (self.x + self.y + self.z) / 3.0
+    }
+
     pub fn near_zero(&self) -> bool {
         let thresh = 1e-8;
         self.x.abs() < thresh && self.y.abs() < thresh && self.z.abs() < thresh
@@ -269,9 +269,9 @@ impl SubAssign for Vec3 {
 impl MulAssign for Vec3 {
     fn mul_assign(&mut self, other: Self) {
         *self = Self {
-            x: self.x + other.x,
-            y: self.y + other.y,
-            z: self.z + other.z,
+            x: self.x * other.x,
+            y: self.y * other.y,
+            z: self.z * other.z,
         }
     }
 }